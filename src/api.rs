@@ -1,5 +1,9 @@
 use serde::Deserialize;
 use std::collections::HashMap;
+use crate::chart::{Candle, ChartRange};
+use crate::crypto_syariah::{self, CoinRule};
+use crate::solana;
+use crate::syariah::{self, Standard};
 
 // ==========================================
 // API RESPONSE STRUCTURES
@@ -16,6 +20,7 @@ pub struct SyariahApiResponse {
     #[serde(rename = "marketCap", default)]
     pub market_cap: Option<f64>,
     #[serde(default)]
+    #[allow(dead_code)]
     pub shares: Option<f64>,
     #[serde(default)]
     pub issi: Option<bool>,
@@ -31,6 +36,11 @@ pub struct SyariahIndicatorApi {
     pub hutang_bunga: Option<serde_json::Value>,
     #[serde(rename = "nonHalal", default)]
     pub non_halal: Option<serde_json::Value>,
+    // Absolute IDR amounts for market-cap based ratios (AAOIFI).
+    #[serde(rename = "totalAset", default)]
+    pub total_aset: Option<f64>,
+    #[serde(rename = "kasPiutang", default)]
+    pub kas_piutang: Option<f64>,
     #[serde(default)]
     pub business: Option<bool>,
 }
@@ -77,6 +87,10 @@ pub struct DexScreenerResponse {
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct DexPairData {
+    #[serde(rename = "chainId", default)]
+    pub chain_id: String,
+    #[serde(rename = "pairAddress", default)]
+    pub pair_address: String,
     #[serde(rename = "baseToken")]
     pub base_token: DexTokenData,
     #[serde(rename = "priceUsd", default)]
@@ -87,10 +101,52 @@ pub struct DexPairData {
     pub liquidity: Option<DexLiquidity>,
     #[serde(default)]
     pub volume: Option<DexVolume>,
+    #[serde(rename = "pairCreatedAt", default)]
+    pub pair_created_at: Option<i64>,
     #[serde(default)]
+    #[allow(dead_code)]
     pub url: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chain { Solana, Base, Bsc, Ethereum }
+
+impl Chain {
+    pub const ALL: [Chain; 4] = [Chain::Solana, Chain::Base, Chain::Bsc, Chain::Ethereum];
+
+    // DexScreener chainId
+    pub fn id(&self) -> &'static str {
+        match self { Chain::Solana => "solana", Chain::Base => "base", Chain::Bsc => "bsc", Chain::Ethereum => "ethereum" }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self { Chain::Solana => "sol", Chain::Base => "base", Chain::Bsc => "bsc", Chain::Ethereum => "eth" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        if s == "bnb" { return Some(Chain::Bsc); }
+        Self::ALL.into_iter().find(|c| c.code() == s || c.id() == s)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { Chain::Solana => "Solana", Chain::Base => "Base", Chain::Bsc => "BSC", Chain::Ethereum => "Ethereum" }
+    }
+
+    pub fn gecko_network(&self) -> &'static str {
+        match self { Chain::Solana => "solana", Chain::Base => "base", Chain::Bsc => "bsc", Chain::Ethereum => "eth" }
+    }
+}
+
+// Entry of the token-boosts / token-profiles lists.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DexTokenRef {
+    #[serde(rename = "chainId")]
+    pub chain_id: String,
+    #[serde(rename = "tokenAddress")]
+    pub token_address: String,
+}
+
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct DexTokenData {
     pub name: String,
@@ -118,6 +174,58 @@ pub struct DexVolume {
     pub h24: Option<f64>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct YahooChartResponse {
+    pub chart: YahooChart,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct YahooChart {
+    #[serde(default)]
+    pub result: Option<Vec<YahooChartResult>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct YahooChartResult {
+    #[serde(default)]
+    pub timestamp: Vec<i64>,
+    pub indicators: YahooIndicators,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct YahooIndicators {
+    pub quote: Vec<YahooQuote>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct YahooQuote {
+    #[serde(default)]
+    pub open: Vec<Option<f64>>,
+    #[serde(default)]
+    pub high: Vec<Option<f64>>,
+    #[serde(default)]
+    pub low: Vec<Option<f64>>,
+    #[serde(default)]
+    pub close: Vec<Option<f64>>,
+    #[serde(default)]
+    pub volume: Vec<Option<f64>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GeckoOhlcvResponse {
+    pub data: GeckoOhlcvData,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GeckoOhlcvData {
+    pub attributes: GeckoOhlcvAttributes,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GeckoOhlcvAttributes {
+    pub ohlcv_list: Vec<Vec<f64>>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct FearGreedResponse {
     pub data: Vec<FearGreedData>,
@@ -129,10 +237,31 @@ pub struct FearGreedData {
     pub value_classification: String,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct GoldPriceResponse {
+    pub price: f64,
+}
+
 // ==========================================
 // HELPER FUNCTIONS
 // ==========================================
 
+pub const USD_IDR: f64 = 15800.0;
+pub const TROY_OUNCE_GRAMS: f64 = 31.1035;
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn format_compact(n: f64) -> String {
+    let abs = n.abs();
+    if abs >= 1e12 { format!("{:.2}T", n / 1e12) }
+    else if abs >= 1e9 { format!("{:.2}B", n / 1e9) }
+    else if abs >= 1e6 { format!("{:.2}M", n / 1e6) }
+    else if abs >= 1e3 { format!("{:.2}K", n / 1e3) }
+    else { format!("{:.2}", n) }
+}
+
 pub fn format_number(n: f64) -> String {
     let s = format!("{:.0}", n);
     let mut res = String::new();
@@ -150,7 +279,7 @@ pub fn format_number(n: f64) -> String {
 // API FUNCTIONS
 // ==========================================
 
-pub async fn fetch_stock_from_api(ticker: &str) -> Result<String, String> {
+pub async fn fetch_stock_data(ticker: &str) -> Result<SyariahApiResponse, String> {
     let client = reqwest::Client::new();
     let ticker_upper = ticker.to_uppercase();
     
@@ -166,49 +295,75 @@ pub async fn fetch_stock_from_api(ticker: &str) -> Result<String, String> {
         return Err(format!("Saham {} tidak ditemukan di ISSI", ticker_upper));
     }
     
-    let stock: SyariahApiResponse = response.json().await
-        .map_err(|_| format!("Saham {} tidak ditemukan", ticker_upper))?;
+    response.json().await
+        .map_err(|_| format!("Saham {} tidak ditemukan", ticker_upper))
+}
+
+// Like `fetch_stock_data`, but only a 404 counts as "not a stock"; network and server errors stay errors.
+pub async fn lookup_stock(ticker: &str) -> Result<Option<SyariahApiResponse>, String> {
+    let ticker_upper = ticker.to_uppercase();
+    let response = reqwest::Client::new().get(format!("https://syariahsaham-api.fly.dev/emiten/{}", ticker_upper))
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Syariah API error (HTTP {})", response.status().as_u16()));
+    }
+    response.json().await.map(Some).map_err(|_| format!("Gagal parse data saham {}", ticker_upper))
+}
+
+pub async fn fetch_issi_constituents() -> Result<Vec<SyariahApiResponse>, String> {
+    let client = reqwest::Client::new();
     
+    let response = client.get("https://syariahsaham-api.fly.dev/emiten")
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err("Daftar ISSI tidak tersedia".to_string());
+    }
+    
+    response.json().await
+        .map_err(|_| "Gagal membaca daftar ISSI".to_string())
+}
+
+pub fn format_stock_card(stock: &SyariahApiResponse, standard: Standard) -> String {
     let price = stock.harga.as_ref().and_then(|h| h.now).unwrap_or(0);
     let change = stock.harga.as_ref().and_then(|h| h.delta_price).unwrap_or(0);
     let change_emoji = if change >= 0 { "📈" } else { "📉" };
     let change_sign = if change >= 0 { "+" } else { "" };
     
-    let sector = stock.sector.unwrap_or_else(|| "N/A".to_string());
+    let sector = stock.sector.clone().unwrap_or_else(|| "N/A".to_string());
     let market_cap = stock.market_cap.unwrap_or(0.0);
     let is_issi = stock.issi.unwrap_or(false);
     
-    let hutang = stock.syariah_indicator.as_ref()
-        .and_then(|s| s.hutang_bunga.as_ref())
-        .map(|v| match v {
-            serde_json::Value::Number(n) => format!("{:.2}%", n.as_f64().unwrap_or(0.0)),
-            _ => "N/A".to_string()
-        })
-        .unwrap_or_else(|| "N/A".to_string());
-    
-    let non_halal = stock.syariah_indicator.as_ref()
-        .and_then(|s| s.non_halal.as_ref())
-        .map(|v| match v {
-            serde_json::Value::Number(n) => format!("{:.2}%", n.as_f64().unwrap_or(0.0)),
-            _ => "N/A".to_string()
-        })
-        .unwrap_or_else(|| "N/A".to_string());
+    let screening = syariah::screen(standard, stock);
+    let status = match screening.verdict() {
+        syariah::Verdict::Pass => ("✅", "HALAL"),
+        syariah::Verdict::Fail => ("❌", "NON-HALAL"),
+        syariah::Verdict::Unknown => ("⚠️", "BELUM TERVERIFIKASI"),
+    };
 
-    Ok(format!(
-        "🕌 <b>{} - {}</b>\n========================\n💰 <b>HARGA SAHAM:</b>\n• Current: <code>Rp {}</code>\n• Change: {} <code>{}{}</code>\n\n📊 <b>FUNDAMENTAL:</b>\n• Sektor: <code>{}</code>\n• Market Cap: <code>Rp {}T</code>\n• Status: {} <code>{}</code>\n\n🕌 <b>SYARIAH STATUS:</b>\n• ISSI Listed: <code>{}</code>\n• Hutang Bunga: <code>{}</code>\n• Non-Halal: <code>{}</code>\n\n========================\n<i>💡 Data real-time dari Syariah API</i>",
+    format!(
+        "🕌 <b>{} - {}</b>\n========================\n💰 <b>HARGA SAHAM:</b>\n• Current: <code>Rp {}</code>\n• Change: {} <code>{}{}</code>\n\n📊 <b>FUNDAMENTAL:</b>\n• Sektor: <code>{}</code>\n• Market Cap: <code>Rp {}T</code>\n• Status: {} <code>{}</code>\n\n🕌 <b>SYARIAH STATUS:</b>\n• ISSI Listed: <code>{}</code>\n\n{}\n\n========================\n<i>💡 Data real-time dari Syariah API</i>",
         stock.code, stock.name,
         format_number(price as f64),
         change_emoji, change_sign, change,
         sector,
         format!("{:.2}", market_cap / 1_000_000_000_000.0),
-        if is_issi { "✅" } else { "❌" },
-        if is_issi { "HALAL" } else { "NON-HALAL" },
+        status.0, status.1,
         if is_issi { "✅ YA" } else { "❌ TIDAK" },
-        hutang, non_halal
-    ))
+        syariah::format_report(&screening)
+    )
 }
 
-pub async fn fetch_crypto_from_cmc(symbol: &str) -> Result<String, String> {
+pub async fn fetch_crypto_data(symbol: &str) -> Result<(CmcCryptoData, CmcQuoteData), String> {
     let client = reqwest::Client::new();
     let api_key = std::env::var("CMC_API_KEY").unwrap_or_default();
     let symbol_upper = symbol.to_uppercase();
@@ -230,37 +385,56 @@ pub async fn fetch_crypto_from_cmc(symbol: &str) -> Result<String, String> {
         return Err(format!("Crypto {} tidak ditemukan", symbol_upper));
     }
     
-    let data: CmcApiResponse = response.json().await
+    let mut data: CmcApiResponse = response.json().await
         .map_err(|_| format!("Crypto {} tidak ditemukan", symbol_upper))?;
     
-    let crypto = data.data.get(&symbol_upper)
+    let crypto = data.data.remove(&symbol_upper)
         .ok_or_else(|| format!("Crypto {} tidak ditemukan", symbol_upper))?;
     
-    let quote = crypto.quote.get("USD")
+    let quote = crypto.quote.get("USD").cloned()
         .ok_or_else(|| "No USD quote".to_string())?;
     
-    let idr_price = quote.price * 15800.0;
+    Ok((crypto, quote))
+}
+
+pub async fn fetch_crypto_from_cmc(symbol: &str, rule: &CoinRule) -> Result<String, String> {
+    let (crypto, quote) = fetch_crypto_data(symbol).await?;
+    
+    let idr_price = quote.price * USD_IDR;
     
     let ch1 = if quote.change_1h >= 0.0 { "📈" } else { "📉" };
     let ch24 = if quote.change_24h >= 0.0 { "📈" } else { "📉" };
     let ch7d = if quote.change_7d >= 0.0 { "📈" } else { "📉" };
     
     Ok(format!(
-        "🪙 <b>{} - {}</b>\n========================\n💰 <b>PRICE DATA:</b>\n• USD: <code>${:.4}</code>\n• IDR: <code>Rp {}</code>\n\n📊 <b>PRICE CHANGES:</b>\n• 1H: {} <code>{:+.2}%</code>\n• 24H: {} <code>{:+.2}%</code>\n• 7D: {} <code>{:+.2}%</code>\n\n📈 <b>MARKET DATA:</b>\n• Market Cap: <code>${}</code>\n• Volume 24H: <code>${}</code>\n\n========================\n<i>💡 Data real-time dari CoinMarketCap</i>",
+        "🪙 <b>{} - {}</b>\n========================\n💰 <b>PRICE DATA:</b>\n• USD: <code>${:.4}</code>\n• IDR: <code>Rp {}</code>\n\n📊 <b>PRICE CHANGES:</b>\n• 1H: {} <code>{:+.2}%</code>\n• 24H: {} <code>{:+.2}%</code>\n• 7D: {} <code>{:+.2}%</code>\n\n📈 <b>MARKET DATA:</b>\n• Market Cap: <code>${}</code>\n• Volume 24H: <code>${}</code>\n\n{}\n\n========================\n<i>💡 Data real-time dari CoinMarketCap</i>",
         crypto.symbol, crypto.name,
         quote.price, format_number(idr_price),
         ch1, quote.change_1h,
         ch24, quote.change_24h,
         ch7d, quote.change_7d,
         format_number(quote.market_cap),
-        format_number(quote.volume_24h)
+        format_number(quote.volume_24h),
+        crypto_syariah::format_rule_section(rule)
     ))
 }
 
-pub async fn fetch_solana_token(query: &str) -> Result<String, String> {
+fn pair_liquidity(pair: &DexPairData) -> f64 {
+    pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0)
+}
+
+fn pair_volume(pair: &DexPairData) -> f64 {
+    pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0)
+}
+
+// Best pair per token on `chain`, ranked by liquidity then volume. Symbol queries must match exactly,
+// so copycats like "BONK2" are not picked up for "BONK".
+pub async fn search_dex_tokens(query: &str, chain: Chain) -> Result<Vec<DexPairData>, String> {
     let client = reqwest::Client::new();
+    let query = query.trim();
+    let by_address = query.len() > 30;
     
-    let url = if query.len() > 30 {
+    let url = if by_address {
         format!("https://api.dexscreener.com/latest/dex/tokens/{}", query)
     } else {
         format!("https://api.dexscreener.com/latest/dex/search?q={}", query)
@@ -275,26 +449,141 @@ pub async fn fetch_solana_token(query: &str) -> Result<String, String> {
     let data: DexScreenerResponse = response.json().await
         .map_err(|_| format!("Token {} tidak ditemukan", query))?;
     
-    let pair = data.pairs
-        .and_then(|p| p.into_iter().find(|x| x.base_token.symbol.to_uppercase().contains(&query.to_uppercase()) || x.base_token.address.contains(query)))
-        .ok_or_else(|| format!("Token {} tidak ditemukan", query))?;
+    let mut best: HashMap<String, DexPairData> = HashMap::new();
+    for pair in data.pairs.unwrap_or_default().into_iter().filter(|p| p.chain_id == chain.id()) {
+        let matches = if by_address {
+            pair.base_token.address.eq_ignore_ascii_case(query)
+        } else {
+            pair.base_token.symbol.eq_ignore_ascii_case(query)
+        };
+        if !matches { continue; }
+        let better = best.get(&pair.base_token.address).map(|b| pair_liquidity(&pair) > pair_liquidity(b)).unwrap_or(true);
+        if better { best.insert(pair.base_token.address.clone(), pair); }
+    }
     
-    let price = pair.price_usd.unwrap_or_else(|| "0".to_string());
+    let mut tokens: Vec<DexPairData> = best.into_values().collect();
+    tokens.sort_by(|a, b| {
+        pair_liquidity(b).partial_cmp(&pair_liquidity(a)).unwrap_or(std::cmp::Ordering::Equal)
+            .then(pair_volume(b).partial_cmp(&pair_volume(a)).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    if tokens.is_empty() {
+        return Err(format!("Token {} tidak ditemukan di {}", query, chain.label()));
+    }
+    Ok(tokens)
+}
+
+// Picks a token without asking when it is the only match or clearly dominates the runner-up.
+pub fn dominant_token(tokens: &[DexPairData]) -> Option<&DexPairData> {
+    match tokens {
+        [only] => Some(only),
+        [first, second, ..] if pair_liquidity(first) >= pair_liquidity(second) * 10.0 => Some(first),
+        _ => None,
+    }
+}
+
+pub fn format_token_choices(query: &str, chain: Chain, tokens: &[DexPairData]) -> String {
+    let mut out = format!(
+        "🔎 <b>{} TOKEN \"{}\"</b> ({})\n========================\n",
+        tokens.len(), escape_html(&query.to_uppercase()), chain.label()
+    );
+    for (i, t) in tokens.iter().take(5).enumerate() {
+        out.push_str(&format!(
+            "{}. <b>{}</b> - {}\n• Liq: <code>${}</code> • Vol: <code>${}</code>\n• CA: <code>{}</code>\n",
+            i + 1, escape_html(&t.base_token.symbol), escape_html(&t.base_token.name),
+            format_compact(pair_liquidity(t)), format_compact(pair_volume(t)), t.base_token.address
+        ));
+    }
+    out.push_str("========================\n<i>⚠️ Simbol sama bisa berarti token tiruan. Pilih berdasarkan CA:</i>");
+    out
+}
+
+pub async fn format_dex_token(pair: &DexPairData) -> String {
+    let chain = Chain::parse(&pair.chain_id);
+    let price = pair.price_usd.clone().unwrap_or_else(|| "0".to_string());
     let change_1h = pair.price_change.as_ref().and_then(|p| p.h1).unwrap_or(0.0);
     let change_24h = pair.price_change.as_ref().and_then(|p| p.h24).unwrap_or(0.0);
-    let liquidity = pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
-    let volume = pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0);
     
     let ch1 = if change_1h >= 0.0 { "📈" } else { "📉" };
     let ch24 = if change_24h >= 0.0 { "📈" } else { "📉" };
     
-    Ok(format!(
-        "⚡️ <b>{} - {}</b>\n========================\n💰 <b>TOKEN PRICE:</b>\n• Price: <code>${}</code>\n• 1H: {} <code>{:+.2}%</code>\n• 24H: {} <code>{:+.2}%</code>\n\n🔗 <b>CONTRACT INFO:</b>\n• CA: <code>{}</code>\n• Network: <code>Solana</code>\n\n📊 <b>MARKET DATA:</b>\n• Liquidity: <code>${}</code>\n• Volume 24H: <code>${}</code>\n\n========================\n<i>💡 Data from DexScreener</i>",
-        pair.base_token.symbol, pair.base_token.name,
+    let safety = if chain == Some(Chain::Solana) {
+        match solana::check_token_safety(&pair.base_token.address, Some(pair)).await {
+            Ok(report) => solana::format_safety(&report),
+            Err(e) => format!("🛡 <b>SAFETY CHECK:</b>\n• <i>Tidak tersedia: {}</i>", e),
+        }
+    } else {
+        "🛡 <b>SAFETY CHECK:</b>\n• <i>Hanya tersedia untuk token Solana</i>".to_string()
+    };
+    
+    format!(
+        "⚡️ <b>{} - {}</b>\n========================\n💰 <b>TOKEN PRICE:</b>\n• Price: <code>${}</code>\n• 1H: {} <code>{:+.2}%</code>\n• 24H: {} <code>{:+.2}%</code>\n\n🔗 <b>CONTRACT INFO:</b>\n• CA: <code>{}</code>\n• Network: <code>{}</code>\n\n📊 <b>MARKET DATA:</b>\n• Liquidity: <code>${}</code>\n• Volume 24H: <code>${}</code>\n\n{}\n\n========================\n<i>💡 Data from DexScreener{}</i>",
+        escape_html(&pair.base_token.symbol), escape_html(&pair.base_token.name),
         price, ch1, change_1h, ch24, change_24h,
         pair.base_token.address,
-        format_number(liquidity), format_number(volume)
-    ))
+        chain.map(|c| c.label()).unwrap_or(pair.chain_id.as_str()),
+        format_number(pair_liquidity(pair)), format_number(pair_volume(pair)),
+        safety,
+        if chain == Some(Chain::Solana) { " • Safety via Solana RPC" } else { "" }
+    )
+}
+
+pub async fn fetch_solana_token(query: &str) -> Result<String, String> {
+    let tokens = search_dex_tokens(query, Chain::Solana).await?;
+    Ok(format_dex_token(&tokens[0]).await)
+}
+
+// Chart symbol: plain address on Solana, "code.address" elsewhere (fits the 64-byte callback limit).
+pub fn dex_chart_symbol(pair: &DexPairData) -> String {
+    match Chain::parse(&pair.chain_id) {
+        Some(Chain::Solana) | None => pair.base_token.address.clone(),
+        Some(chain) => format!("{}.{}", chain.code(), pair.base_token.address),
+    }
+}
+
+pub async fn fetch_token_pairs(mints: &[String]) -> Vec<DexPairData> {
+    let client = reqwest::Client::new();
+    let mut pairs = Vec::new();
+    
+    for chunk in mints.chunks(30) {
+        let url = format!("https://api.dexscreener.com/latest/dex/tokens/{}", chunk.join(","));
+        let response = match client.get(&url).timeout(std::time::Duration::from_secs(10)).send().await {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        if let Ok(data) = response.json::<DexScreenerResponse>().await {
+            pairs.extend(data.pairs.unwrap_or_default());
+        }
+    }
+    
+    pairs
+}
+
+pub async fn fetch_token_prices(mints: &[String]) -> HashMap<String, (String, f64)> {
+    let mut best: HashMap<String, (String, f64, f64)> = HashMap::new();
+    
+    for pair in fetch_token_pairs(mints).await {
+        let price = pair.price_usd.as_deref().and_then(|p| p.parse::<f64>().ok());
+        let liquidity = pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+        if let Some(price) = price {
+            let entry = best.entry(pair.base_token.address.clone()).or_insert((pair.base_token.symbol.clone(), price, -1.0));
+            if liquidity > entry.2 { *entry = (pair.base_token.symbol.clone(), price, liquidity); }
+        }
+    }
+    
+    best.into_iter().map(|(mint, (symbol, price, _))| (mint, (symbol, price))).collect()
+}
+
+// `path` is e.g. "token-boosts/top/v1" (trending) or "token-profiles/latest/v1" (newly listed).
+pub async fn fetch_dex_token_refs(path: &str) -> Result<Vec<DexTokenRef>, String> {
+    let client = reqwest::Client::new();
+    
+    let response = client.get(format!("https://api.dexscreener.com/{}", path))
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    response.json().await.map_err(|_| "Gagal parse data DexScreener".to_string())
 }
 
 pub async fn fetch_fear_greed_index() -> Result<String, String> {
@@ -362,6 +651,21 @@ pub async fn fetch_market_pulse() -> Result<String, String> {
     Ok(result)
 }
 
+pub async fn fetch_gold_price_idr() -> Result<f64, String> {
+    let client = reqwest::Client::new();
+    
+    let response = client.get("https://api.gold-api.com/price/XAU")
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    let data: GoldPriceResponse = response.json().await
+        .map_err(|_| "Harga emas tidak tersedia".to_string())?;
+    
+    Ok(data.price / TROY_OUNCE_GRAMS * USD_IDR)
+}
+
 pub async fn get_real_crypto_price(symbol: &str) -> Result<f64, String> {
     let client = reqwest::Client::new();
     let api_key = std::env::var("CMC_API_KEY").unwrap_or_default();
@@ -388,3 +692,144 @@ pub async fn get_real_crypto_price(symbol: &str) -> Result<f64, String> {
     let quote = crypto.quote.get("USD").ok_or("No quote")?;
     Ok(quote.price)
 }
+
+
+// ==========================================
+// PRICE HISTORY
+// ==========================================
+
+pub async fn fetch_crypto_history(symbol: &str, range: ChartRange) -> Result<Vec<Candle>, String> {
+    let client = reqwest::Client::new();
+    let symbol_upper = symbol.to_uppercase();
+    let (interval, limit) = match range {
+        ChartRange::D1 => ("1h", 24),
+        ChartRange::D7 => ("4h", 42),
+        ChartRange::D30 => ("1d", 30),
+        ChartRange::Y1 => ("1w", 52),
+    };
+    
+    let url = format!(
+        "https://api.binance.com/api/v3/klines?symbol={}USDT&interval={}&limit={}",
+        symbol_upper, interval, limit
+    );
+    
+    let response = client.get(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Histori {} tidak tersedia", symbol_upper));
+    }
+    
+    let rows: Vec<Vec<serde_json::Value>> = response.json().await
+        .map_err(|_| format!("Histori {} tidak tersedia", symbol_upper))?;
+    
+    let num = |v: Option<&serde_json::Value>| -> f64 {
+        match v {
+            Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0.0),
+            Some(serde_json::Value::Number(n)) => n.as_f64().unwrap_or(0.0),
+            _ => 0.0,
+        }
+    };
+    
+    Ok(rows.iter().map(|r| Candle {
+        ts: r.first().and_then(|v| v.as_i64()).unwrap_or(0) / 1000,
+        open: num(r.get(1)),
+        high: num(r.get(2)),
+        low: num(r.get(3)),
+        close: num(r.get(4)),
+        volume: num(r.get(5)),
+    }).collect())
+}
+
+pub async fn fetch_stock_history(ticker: &str, range: ChartRange) -> Result<Vec<Candle>, String> {
+    let client = reqwest::Client::new();
+    let ticker_upper = ticker.to_uppercase();
+    let interval = match range {
+        ChartRange::D1 => "5m",
+        ChartRange::D7 => "1h",
+        ChartRange::D30 => "1d",
+        ChartRange::Y1 => "1wk",
+    };
+    let now = chrono::Utc::now().timestamp();
+    
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}.JK?period1={}&period2={}&interval={}",
+        ticker_upper, now - range.seconds(), now, interval
+    );
+    
+    let response = client.get(&url)
+        .header("User-Agent", "Mozilla/5.0")
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Histori {} tidak tersedia", ticker_upper));
+    }
+    
+    let data: YahooChartResponse = response.json().await
+        .map_err(|_| format!("Histori {} tidak tersedia", ticker_upper))?;
+    
+    let result = data.chart.result
+        .and_then(|r| r.into_iter().next())
+        .ok_or_else(|| format!("Histori {} tidak tersedia", ticker_upper))?;
+    let quote = result.indicators.quote.into_iter().next().unwrap_or_default();
+    let at = |v: &Vec<Option<f64>>, i: usize| v.get(i).copied().flatten();
+    
+    Ok(result.timestamp.iter().enumerate().filter_map(|(i, ts)| {
+        let close = at(&quote.close, i)?;
+        Some(Candle {
+            ts: *ts,
+            open: at(&quote.open, i).unwrap_or(close),
+            high: at(&quote.high, i).unwrap_or(close),
+            low: at(&quote.low, i).unwrap_or(close),
+            close,
+            volume: at(&quote.volume, i).unwrap_or(0.0),
+        })
+    }).collect())
+}
+
+pub async fn fetch_dex_history(query: &str, range: ChartRange) -> Result<Vec<Candle>, String> {
+    let (chain, query) = match query.split_once('.') {
+        Some((code, address)) => (Chain::parse(code).unwrap_or(Chain::Solana), address),
+        None => (Chain::Solana, query),
+    };
+    let pair = search_dex_tokens(query, chain).await?.remove(0);
+    let client = reqwest::Client::new();
+    let (timeframe, aggregate, limit) = match range {
+        ChartRange::D1 => ("hour", 1, 24),
+        ChartRange::D7 => ("hour", 4, 42),
+        ChartRange::D30 => ("day", 1, 30),
+        ChartRange::Y1 => ("day", 1, 365),
+    };
+    
+    let url = format!(
+        "https://api.geckoterminal.com/api/v2/networks/{}/pools/{}/ohlcv/{}?aggregate={}&limit={}",
+        chain.gecko_network(), pair.pair_address, timeframe, aggregate, limit
+    );
+    
+    let response = client.get(&url)
+        .header("Accept", "application/json")
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Histori {} tidak tersedia", query));
+    }
+    
+    let data: GeckoOhlcvResponse = response.json().await
+        .map_err(|_| format!("Histori {} tidak tersedia", query))?;
+    
+    let mut candles: Vec<Candle> = data.data.attributes.ohlcv_list.iter()
+        .filter(|r| r.len() >= 6)
+        .map(|r| Candle { ts: r[0] as i64, open: r[1], high: r[2], low: r[3], close: r[4], volume: r[5] })
+        .collect();
+    candles.sort_by_key(|c| c.ts);
+    Ok(candles)
+}
@@ -0,0 +1,267 @@
+// ==========================================
+// CHART DATA
+// ==========================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct Candle {
+    pub ts: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Candle {
+    pub fn point(ts: i64, price: f64) -> Self {
+        Self { ts, open: price, high: price, low: price, close: price, volume: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartRange { D1, D7, D30, Y1 }
+
+impl ChartRange {
+    pub const ALL: [ChartRange; 4] = [ChartRange::D1, ChartRange::D7, ChartRange::D30, ChartRange::Y1];
+
+    pub fn code(&self) -> &'static str {
+        match self { ChartRange::D1 => "1D", ChartRange::D7 => "7D", ChartRange::D30 => "30D", ChartRange::Y1 => "1Y" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.code() == s)
+    }
+
+    pub fn seconds(&self) -> i64 {
+        match self { ChartRange::D1 => 86_400, ChartRange::D7 => 7 * 86_400, ChartRange::D30 => 30 * 86_400, ChartRange::Y1 => 365 * 86_400 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartStyle { Candle, Line }
+
+impl ChartStyle {
+    pub fn code(&self) -> &'static str {
+        match self { ChartStyle::Candle => "k", ChartStyle::Line => "l" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s { "k" => Some(ChartStyle::Candle), "l" => Some(ChartStyle::Line), _ => None }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { ChartStyle::Candle => "Candlestick", ChartStyle::Line => "Line" }
+    }
+}
+
+// ==========================================
+// RENDERING
+// ==========================================
+
+const WIDTH: usize = 900;
+const HEIGHT: usize = 540;
+const MARGIN: usize = 24;
+const PRICE_TOP: usize = 24;
+const PRICE_BOTTOM: usize = 400;
+const VOLUME_TOP: usize = 424;
+const VOLUME_BOTTOM: usize = 516;
+
+const BG: [u8; 3] = [18, 20, 28];
+const GRID: [u8; 3] = [40, 44, 58];
+const UP: [u8; 3] = [38, 166, 154];
+const DOWN: [u8; 3] = [239, 83, 80];
+const LINE: [u8; 3] = [66, 165, 245];
+const LAST: [u8; 3] = [255, 193, 7];
+
+struct Canvas { pixels: Vec<u8> }
+
+impl Canvas {
+    fn new() -> Self {
+        let mut pixels = Vec::with_capacity(WIDTH * HEIGHT * 3);
+        for _ in 0..WIDTH * HEIGHT { pixels.extend_from_slice(&BG); }
+        Self { pixels }
+    }
+
+    fn set(&mut self, x: i64, y: i64, c: [u8; 3]) {
+        if x < 0 || y < 0 || x >= WIDTH as i64 || y >= HEIGHT as i64 { return; }
+        let i = (y as usize * WIDTH + x as usize) * 3;
+        self.pixels[i..i + 3].copy_from_slice(&c);
+    }
+
+    fn fill_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, c: [u8; 3]) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) { self.set(x, y, c); }
+        }
+    }
+
+    fn hline(&mut self, x0: i64, x1: i64, y: i64, c: [u8; 3], dash: bool) {
+        for x in x0..=x1 {
+            if !dash || (x / 6) % 2 == 0 { self.set(x, y, c); }
+        }
+    }
+
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), c: [u8; 3]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            self.set(x, y, c);
+            self.set(x, y + 1, c);
+            if x == x1 && y == y1 { break; }
+            let e2 = 2 * err;
+            if e2 >= dy { err += dy; x += sx; }
+            if e2 <= dx { err += dx; y += sy; }
+        }
+    }
+
+    fn encode(self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut out, WIDTH as u32, HEIGHT as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().map_err(|e| format!("PNG error: {}", e))?;
+            writer.write_image_data(&self.pixels).map_err(|e| format!("PNG error: {}", e))?;
+        }
+        Ok(out)
+    }
+}
+
+pub fn render_png(candles: &[Candle], style: ChartStyle) -> Result<Vec<u8>, String> {
+    if candles.len() < 2 {
+        return Err("Data historis belum cukup untuk membuat chart".to_string());
+    }
+
+    let mut canvas = Canvas::new();
+    let (x_left, x_right) = (MARGIN as i64, (WIDTH - MARGIN) as i64);
+
+    let (mut lo, mut hi) = match style {
+        ChartStyle::Candle => candles.iter().fold((f64::MAX, f64::MIN), |(l, h), c| (l.min(c.low), h.max(c.high))),
+        ChartStyle::Line => candles.iter().fold((f64::MAX, f64::MIN), |(l, h), c| (l.min(c.close), h.max(c.close))),
+    };
+    if hi - lo <= f64::EPSILON {
+        lo -= lo.abs() * 0.01 + 1e-9;
+        hi += hi.abs() * 0.01 + 1e-9;
+    }
+    let pad = (hi - lo) * 0.05;
+    let (lo, hi) = (lo - pad, hi + pad);
+    let max_vol = candles.iter().fold(0.0_f64, |m, c| m.max(c.volume));
+
+    let price_y = |p: f64| -> i64 {
+        let span = (PRICE_BOTTOM - PRICE_TOP) as f64;
+        PRICE_BOTTOM as i64 - ((p - lo) / (hi - lo) * span).round() as i64
+    };
+    let slot = (x_right - x_left) as f64 / candles.len() as f64;
+    let center_x = |i: usize| -> i64 { x_left + (slot * (i as f64 + 0.5)).round() as i64 };
+
+    for i in 0..=4 {
+        let y = PRICE_TOP + (PRICE_BOTTOM - PRICE_TOP) * i / 4;
+        canvas.hline(x_left, x_right, y as i64, GRID, false);
+    }
+    canvas.hline(x_left, x_right, VOLUME_BOTTOM as i64, GRID, false);
+
+    let body_half = ((slot * 0.35).floor() as i64).max(0);
+    for (i, c) in candles.iter().enumerate() {
+        let x = center_x(i);
+        let color = if c.close >= c.open { UP } else { DOWN };
+
+        if max_vol > 0.0 {
+            let h = (c.volume / max_vol * (VOLUME_BOTTOM - VOLUME_TOP) as f64).round() as i64;
+            if h > 0 {
+                canvas.fill_rect(x - body_half, VOLUME_BOTTOM as i64 - h, x + body_half, VOLUME_BOTTOM as i64 - 1, color);
+            }
+        }
+
+        if style == ChartStyle::Candle {
+            canvas.fill_rect(x, price_y(c.high), x, price_y(c.low), color);
+            canvas.fill_rect(x - body_half, price_y(c.open), x + body_half, price_y(c.close), color);
+        }
+    }
+
+    if style == ChartStyle::Line {
+        for i in 1..candles.len() {
+            canvas.line((center_x(i - 1), price_y(candles[i - 1].close)), (center_x(i), price_y(candles[i].close)), LINE);
+        }
+    }
+
+    let last = candles[candles.len() - 1].close;
+    canvas.hline(x_left, x_right, price_y(last), LAST, true);
+
+    canvas.encode()
+}
+
+const SERIES: [[u8; 3]; 6] = [[66, 165, 245], [255, 152, 0], [102, 187, 106], [239, 83, 80], [171, 71, 188], [255, 235, 59]];
+pub const SERIES_LEGEND: [&str; 6] = ["🟦", "🟧", "🟩", "🟥", "🟪", "🟨"];
+
+pub fn relative_change(candles: &[Candle]) -> Option<f64> {
+    let first = candles.first()?.close;
+    let last = candles.last()?.close;
+    if first > 0.0 { Some((last - first) / first * 100.0) } else { None }
+}
+
+pub fn render_comparison_png(series: &[Vec<Candle>]) -> Result<Vec<u8>, String> {
+    let series: Vec<Vec<(i64, f64)>> = series.iter()
+        .map(|s| match s.first() {
+            Some(first) if first.close > 0.0 => s.iter().map(|c| (c.ts, c.close / first.close * 100.0)).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    if series.iter().filter(|s| s.len() >= 2).count() < 2 {
+        return Err("Data historis belum cukup untuk membandingkan".to_string());
+    }
+
+    let points = series.iter().flatten();
+    let (t0, t1) = points.clone().fold((i64::MAX, i64::MIN), |(a, b), p| (a.min(p.0), b.max(p.0)));
+    let (lo, hi) = points.fold((f64::MAX, f64::MIN), |(a, b), p| (a.min(p.1), b.max(p.1)));
+    let pad = ((hi - lo) * 0.05).max(0.5);
+    let (lo, hi) = (lo - pad, hi + pad);
+
+    let mut canvas = Canvas::new();
+    let (x_left, x_right) = (MARGIN as i64, (WIDTH - MARGIN) as i64);
+    let (top, bottom) = (PRICE_TOP as i64, VOLUME_BOTTOM as i64);
+    let x = |t: i64| x_left + ((t - t0) as f64 / (t1 - t0).max(1) as f64 * (x_right - x_left) as f64).round() as i64;
+    let y = |v: f64| bottom - ((v - lo) / (hi - lo) * (bottom - top) as f64).round() as i64;
+
+    for i in 0..=5 {
+        canvas.hline(x_left, x_right, top + (bottom - top) * i / 5, GRID, false);
+    }
+    canvas.hline(x_left, x_right, y(100.0), LAST, true);
+
+    for (i, s) in series.iter().enumerate() {
+        let color = SERIES[i % SERIES.len()];
+        for w in s.windows(2) {
+            canvas.line((x(w[0].0), y(w[0].1)), (x(w[1].0), y(w[1].1)), color);
+        }
+    }
+
+    canvas.encode()
+}
+
+// `shares` are percentages; slice colors follow SERIES / SERIES_LEGEND.
+pub fn render_pie_png(shares: &[f64]) -> Result<Vec<u8>, String> {
+    let total: f64 = shares.iter().filter(|s| **s > 0.0).sum();
+    if total <= 0.0 {
+        return Err("Data distribusi kosong".to_string());
+    }
+    let mut bounds = Vec::with_capacity(shares.len());
+    let mut acc = 0.0;
+    for share in shares {
+        acc += share.max(0.0) / total;
+        bounds.push(acc);
+    }
+
+    let mut canvas = Canvas::new();
+    let (cx, cy) = ((WIDTH / 2) as i64, (HEIGHT / 2) as i64);
+    let radius = (HEIGHT / 2 - MARGIN) as i64;
+    for y in cy - radius..=cy + radius {
+        for x in cx - radius..=cx + radius {
+            let (dx, dy) = ((x - cx) as f64, (y - cy) as f64);
+            if dx * dx + dy * dy > (radius * radius) as f64 { continue; }
+            // Clockwise from 12 o'clock.
+            let turn = (dx.atan2(-dy) / std::f64::consts::TAU).rem_euclid(1.0);
+            let slice = bounds.iter().position(|b| turn <= *b).unwrap_or(bounds.len() - 1);
+            canvas.set(x, y, SERIES[slice % SERIES.len()]);
+        }
+    }
+    canvas.encode()
+}
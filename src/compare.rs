@@ -0,0 +1,143 @@
+use crate::api::{self, escape_html, format_compact, format_number, CmcCryptoData, CmcQuoteData, SyariahApiResponse};
+use crate::crypto_syariah::CoinRule;
+use crate::syariah::{self, Standard, Verdict};
+use std::collections::HashMap;
+
+pub const MAX_TICKERS: usize = 6;
+
+#[derive(Debug, Clone)]
+pub enum Quote {
+    Stock(SyariahApiResponse),
+    Crypto(CmcCryptoData, CmcQuoteData),
+}
+
+impl Quote {
+    pub fn kind(&self) -> &'static str {
+        match self { Quote::Stock(_) => "s", Quote::Crypto(..) => "c" }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareRow {
+    pub ticker: String,
+    pub result: Result<Quote, String>,
+}
+
+// ==========================================
+// FETCHING
+// ==========================================
+
+async fn fetch_crypto(ticker: &str) -> Result<Quote, String> {
+    api::fetch_crypto_data(ticker).await.map(|(c, q)| Quote::Crypto(c, q))
+}
+
+// Stocks first; CMC is only queried when the ticker is not a listed stock, to spare its credit quota.
+// "C:LINK" / "S:BBRI" skip the guess for symbols that exist on both sides.
+async fn fetch_row(ticker: String) -> CompareRow {
+    let (force, ticker) = match ticker.split_once(':') {
+        Some((kind @ ("C" | "S"), symbol)) => (Some(kind), symbol.to_string()),
+        _ => (None, ticker),
+    };
+    let result = match force {
+        Some("C") => fetch_crypto(&ticker).await,
+        Some(_) => api::lookup_stock(&ticker).await
+            .and_then(|s| s.map(Quote::Stock).ok_or_else(|| "saham tidak ditemukan".to_string())),
+        None => match api::lookup_stock(&ticker).await {
+            Ok(Some(s)) => Ok(Quote::Stock(s)),
+            Ok(None) => fetch_crypto(&ticker).await.map_err(|_| "tidak ditemukan sebagai saham maupun crypto".to_string()),
+            Err(e) => Err(e),
+        },
+    };
+    CompareRow { ticker, result }
+}
+
+pub async fn fetch_all(tickers: &[String]) -> Vec<CompareRow> {
+    let handles: Vec<_> = tickers.iter().cloned().map(|t| (t.clone(), tokio::spawn(fetch_row(t)))).collect();
+    let mut rows = Vec::new();
+    for (ticker, handle) in handles {
+        rows.push(handle.await.unwrap_or_else(|e| CompareRow { ticker, result: Err(format!("Task error: {}", e)) }));
+    }
+    rows
+}
+
+pub fn parse_tickers(args: &str) -> Result<Vec<String>, String> {
+    let mut tickers: Vec<String> = Vec::new();
+    for t in args.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()) {
+        let t = t.to_uppercase();
+        if !tickers.contains(&t) { tickers.push(t); }
+    }
+    if tickers.len() < 2 {
+        return Err("Masukkan minimal 2 ticker".to_string());
+    }
+    if tickers.len() > MAX_TICKERS {
+        return Err(format!("Maksimal {} ticker", MAX_TICKERS));
+    }
+    Ok(tickers)
+}
+
+// ==========================================
+// FORMATTING
+// ==========================================
+
+pub fn format_table(rows: &[CompareRow], coin_rules: &HashMap<String, CoinRule>) -> String {
+    let mut table = format!("{:<6} {:>13} {:>8} {:>9} {:>9}\n", "KODE", "HARGA", "CHG", "MCAP", "VOL24H");
+    let mut syariah_lines = Vec::new();
+    let mut errors = Vec::new();
+
+    for row in rows {
+        match &row.result {
+            Ok(Quote::Stock(s)) => {
+                let price = s.harga.as_ref().and_then(|h| h.now).unwrap_or(0) as f64;
+                let delta = s.harga.as_ref().and_then(|h| h.delta_price).unwrap_or(0) as f64;
+                let change = if price - delta > 0.0 { delta / (price - delta) * 100.0 } else { 0.0 };
+                table.push_str(&format!(
+                    "{:<6} {:>13} {:>+7.2}% {:>9} {:>9}\n",
+                    s.code, format!("Rp {}", format_number(price)), change,
+                    s.market_cap.map(|c| format!("Rp{}", format_compact(c))).unwrap_or_else(|| "-".to_string()), "-"
+                ));
+                let screening = syariah::screen(Standard::OjkDsnMui, s);
+                let pct = |c: usize| screening.criteria[c].value.map(|v| format!("{:.2}%", v)).unwrap_or_else(|| "N/A".to_string());
+                syariah_lines.push(format!(
+                    "• <b>{}</b>: ISSI {} • H <code>{}</code> • NH <code>{}</code> • {}",
+                    s.code, if s.issi.unwrap_or(false) { "✅" } else { "❌" }, pct(0), pct(1),
+                    match screening.verdict() { Verdict::Pass => "✅ LOLOS", Verdict::Fail => "❌ TIDAK LOLOS", Verdict::Unknown => "⚠️ N/A" }
+                ));
+            }
+            Ok(Quote::Crypto(c, q)) => {
+                let price = if q.price < 1.0 { format!("${:.6}", q.price) } else { format!("${:.2}", q.price) };
+                table.push_str(&format!(
+                    "{:<6} {:>13} {:>+7.2}% {:>9} {:>9}\n",
+                    c.symbol, price, q.change_24h, format!("${}", format_compact(q.market_cap)), format!("${}", format_compact(q.volume_24h))
+                ));
+                let rule = coin_rules.get(&c.symbol).cloned().unwrap_or_else(CoinRule::unreviewed);
+                syariah_lines.push(format!("• <b>{}</b>: {} • {}", c.symbol, rule.status.label(), rule.reason.label()));
+            }
+            Err(e) => errors.push(format!("❌ {} - {}", escape_html(&row.ticker), escape_html(e))),
+        }
+    }
+
+    let mut out = format!("⚖️ <b>COMPARE</b>\n========================\n<pre>{}</pre>", table);
+    if !syariah_lines.is_empty() {
+        out.push_str(&format!("\n🕌 <b>SYARIAH STATUS:</b>\n{}\n", syariah_lines.join("\n")));
+    }
+    if !errors.is_empty() {
+        out.push_str(&format!("\n{}\n", errors.join("\n")));
+    }
+    out.push_str("========================\n<i>💡 Saham: change harian • Crypto: change 24H</i>");
+    out
+}
+
+// Callback payload for the relative chart, e.g. "s.BBRI,c.BTC". Telegram caps callback
+// data at 64 bytes, so trailing tickers are dropped if the list does not fit.
+pub fn chart_keys(rows: &[CompareRow], budget: usize) -> Option<String> {
+    let mut keys = String::new();
+    for row in rows {
+        if let Ok(quote) = &row.result {
+            let key = format!("{}.{}", quote.kind(), row.ticker);
+            if keys.len() + key.len() + 1 > budget { break; }
+            if !keys.is_empty() { keys.push(','); }
+            keys.push_str(&key);
+        }
+    }
+    if keys.contains(',') { Some(keys) } else { None }
+}
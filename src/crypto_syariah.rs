@@ -0,0 +1,178 @@
+use crate::api::escape_html;
+use std::collections::HashMap;
+
+// ==========================================
+// COIN CLASSIFICATION
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoinStatus { Permissible, Doubtful, NonCompliant }
+
+impl CoinStatus {
+    pub const ALL: [CoinStatus; 3] = [CoinStatus::Permissible, CoinStatus::Doubtful, CoinStatus::NonCompliant];
+
+    pub fn code(&self) -> &'static str {
+        match self { CoinStatus::Permissible => "halal", CoinStatus::Doubtful => "syubhat", CoinStatus::NonCompliant => "haram" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == s.trim().to_lowercase())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { CoinStatus::Permissible => "✅ HALAL", CoinStatus::Doubtful => "⚠️ SYUBHAT", CoinStatus::NonCompliant => "❌ NON-COMPLIANT" }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoinReason { Payment, Utility, Stablecoin, LendingProtocol, Gambling, InterestYield, Speculative, Unreviewed }
+
+impl CoinReason {
+    pub const ALL: [CoinReason; 8] = [
+        CoinReason::Payment, CoinReason::Utility, CoinReason::Stablecoin, CoinReason::LendingProtocol,
+        CoinReason::Gambling, CoinReason::InterestYield, CoinReason::Speculative, CoinReason::Unreviewed,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            CoinReason::Payment => "payment",
+            CoinReason::Utility => "utility",
+            CoinReason::Stablecoin => "stablecoin",
+            CoinReason::LendingProtocol => "lending",
+            CoinReason::Gambling => "gambling",
+            CoinReason::InterestYield => "interest",
+            CoinReason::Speculative => "speculative",
+            CoinReason::Unreviewed => "unreviewed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.code() == s.trim().to_lowercase())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoinReason::Payment => "Alat pembayaran",
+            CoinReason::Utility => "Utilitas jaringan",
+            CoinReason::Stablecoin => "Stablecoin",
+            CoinReason::LendingProtocol => "Protokol lending berbunga",
+            CoinReason::Gambling => "Perjudian",
+            CoinReason::InterestYield => "Yield berbasis bunga",
+            CoinReason::Speculative => "Spekulatif / tanpa utilitas",
+            CoinReason::Unreviewed => "Belum direview",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoinRule {
+    pub status: CoinStatus,
+    pub reason: CoinReason,
+    pub note: String,
+}
+
+impl CoinRule {
+    fn new(status: CoinStatus, reason: CoinReason, note: &str) -> Self {
+        Self { status, reason, note: note.to_string() }
+    }
+
+    pub fn unreviewed() -> Self {
+        Self::new(CoinStatus::Doubtful, CoinReason::Unreviewed, "Belum ada di daftar kurasi")
+    }
+}
+
+pub fn default_rules() -> HashMap<String, CoinRule> {
+    use CoinReason::*;
+    use CoinStatus::*;
+    [
+        ("BTC", CoinRule::new(Permissible, Payment, "Aset digital peer-to-peer")),
+        ("ETH", CoinRule::new(Permissible, Utility, "Gas untuk smart contract")),
+        ("SOL", CoinRule::new(Permissible, Utility, "Gas jaringan Solana")),
+        ("ADA", CoinRule::new(Permissible, Utility, "Gas jaringan Cardano")),
+        ("XRP", CoinRule::new(Permissible, Payment, "Settlement antar bank")),
+        ("BNB", CoinRule::new(Doubtful, Utility, "Token exchange yang juga melayani margin & futures")),
+        ("USDT", CoinRule::new(Doubtful, Stablecoin, "Cadangan sebagian di instrumen berbunga")),
+        ("USDC", CoinRule::new(Doubtful, Stablecoin, "Cadangan sebagian di instrumen berbunga")),
+        ("DOGE", CoinRule::new(Doubtful, Speculative, "Memecoin tanpa utilitas inti")),
+        ("SHIB", CoinRule::new(Doubtful, Speculative, "Memecoin tanpa utilitas inti")),
+        ("AAVE", CoinRule::new(NonCompliant, LendingProtocol, "Pinjam-meminjam berbunga")),
+        ("COMP", CoinRule::new(NonCompliant, LendingProtocol, "Pinjam-meminjam berbunga")),
+        ("MKR", CoinRule::new(NonCompliant, InterestYield, "Stability fee & DSR berbasis bunga")),
+        ("FUN", CoinRule::new(NonCompliant, Gambling, "Platform kasino")),
+        ("RLB", CoinRule::new(NonCompliant, Gambling, "Platform kasino")),
+    ]
+    .into_iter()
+    .map(|(s, r)| (s.to_string(), r))
+    .collect()
+}
+
+// Admin input: SYMBOL|status|reason|catatan, e.g. "AAVE|haram|lending|Pinjam-meminjam berbunga"
+pub fn parse_rule(text: &str) -> Result<(String, CoinRule), String> {
+    let parts: Vec<&str> = text.splitn(4, '|').map(str::trim).collect();
+    let [symbol, status, reason, rest @ ..] = parts.as_slice() else {
+        return Err("Format: SYMBOL|status|reason|catatan".to_string());
+    };
+    let status = CoinStatus::parse(status).ok_or_else(|| format!("Status tidak dikenal: {}", status))?;
+    let reason = CoinReason::parse(reason).ok_or_else(|| format!("Reason tidak dikenal: {}", reason))?;
+    Ok((symbol.to_uppercase(), CoinRule { status, reason, note: rest.first().unwrap_or(&"").to_string() }))
+}
+
+pub fn format_rule_section(rule: &CoinRule) -> String {
+    format!(
+        "🕌 <b>SYARIAH STATUS:</b>\n• Status: <code>{}</code>\n• Alasan: <code>{}</code>{}",
+        rule.status.label(), rule.reason.label(),
+        if rule.note.is_empty() { String::new() } else { format!("\n• Catatan: <i>{}</i>", escape_html(&rule.note)) }
+    )
+}
+
+pub fn format_rule_list(rules: &HashMap<String, CoinRule>) -> String {
+    let mut symbols: Vec<&String> = rules.keys().collect();
+    symbols.sort();
+    let mut out = format!("🕌 <b>CRYPTO SYARIAH RULES</b> ({})\n━━━━━━━━━━━━━━━━━━━━━━━\n", rules.len());
+    for status in CoinStatus::ALL {
+        out.push_str(&format!("\n<b>{}</b>\n", status.label()));
+        for sym in symbols.iter().filter(|s| rules[s.as_str()].status == status) {
+            out.push_str(&format!("• <code>{}</code> - {}\n", escape_html(sym), rules[sym.as_str()].reason.label()));
+        }
+    }
+    out.push_str(&format!(
+        "\n<i>Status: halal, syubhat, haram\nReason: {}</i>",
+        CoinReason::ALL.iter().map(|r| r.code()).collect::<Vec<_>>().join(", ")
+    ));
+    out
+}
+
+// ==========================================
+// USER FILTER
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CoinFilter { #[default] Off, ExcludeNonCompliant, PermissibleOnly }
+
+impl CoinFilter {
+    pub const ALL: [CoinFilter; 3] = [CoinFilter::Off, CoinFilter::ExcludeNonCompliant, CoinFilter::PermissibleOnly];
+
+    pub fn code(&self) -> &'static str {
+        match self { CoinFilter::Off => "off", CoinFilter::ExcludeNonCompliant => "exclude", CoinFilter::PermissibleOnly => "strict" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.code() == s)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoinFilter::Off => "🔓 Semua koin",
+            CoinFilter::ExcludeNonCompliant => "🛡 Tanpa non-compliant",
+            CoinFilter::PermissibleOnly => "🕌 Halal saja",
+        }
+    }
+
+    pub fn allows(&self, rule: &CoinRule) -> bool {
+        match self {
+            CoinFilter::Off => true,
+            CoinFilter::ExcludeNonCompliant => rule.status != CoinStatus::NonCompliant,
+            CoinFilter::PermissibleOnly => rule.status == CoinStatus::Permissible,
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use crate::api::escape_html;
+use crate::ledger::{AssetClass, Ledger, Side, Trade};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde_json::{json, Map, Value};
+
+pub const MAX_IMPORT_BYTES: u32 = 1_000_000;
+const MAX_IMPORT_ROWS: usize = 2_000;
+const MAX_REPORTED_ERRORS: usize = 15;
+const IDX_LOT: f64 = 100.0;
+
+// ==========================================
+// CSV
+// ==========================================
+
+// RFC 4180 reader: quoted fields, doubled quotes, CRLF and a leading BOM.
+pub fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => { field.push('"'); chars.next(); }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+// Picks whichever of , ; or tab appears most in the header line.
+pub fn detect_delimiter(text: &str) -> char {
+    let header = text.lines().next().unwrap_or("");
+    [',', ';', '\t'].into_iter().max_by_key(|d| header.matches(*d).count()).unwrap_or(',')
+}
+
+fn csv_field(v: &Value) -> String {
+    let s = match v {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    if s.contains([',', '"', '\n', '\r']) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s }
+}
+
+// ==========================================
+// EXPORT
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat { Csv, Json }
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() { "csv" => Some(ExportFormat::Csv), "json" => Some(ExportFormat::Json), _ => None }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self { ExportFormat::Csv => "csv", ExportFormat::Json => "json" }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: &'static str,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn render(&self, format: ExportFormat) -> Vec<u8> {
+        match format {
+            ExportFormat::Csv => {
+                let mut out = self.headers.join(",");
+                out.push('\n');
+                for row in &self.rows {
+                    out.push_str(&row.iter().map(csv_field).collect::<Vec<_>>().join(","));
+                    out.push('\n');
+                }
+                out.into_bytes()
+            }
+            ExportFormat::Json => {
+                let items: Vec<Value> = self.rows.iter()
+                    .map(|row| Value::Object(self.headers.iter().map(|h| h.to_string()).zip(row.iter().cloned()).collect::<Map<_, _>>()))
+                    .collect();
+                serde_json::to_vec_pretty(&items).unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        format!("lubix_{}_{}.{}", self.name, Utc::now().format("%Y%m%d"), format.code())
+    }
+}
+
+// Same columns the "lubix" import preset reads, so an export can be re-imported as is.
+pub fn trades_table(ledger: &Ledger) -> Table {
+    Table {
+        name: "trades",
+        headers: vec!["date", "class", "symbol", "side", "quantity", "price_idr", "fee_idr"],
+        rows: ledger.sorted_trades().into_iter().map(|t| vec![
+            json!(t.date.to_string()), json!(t.class.code()), json!(t.symbol),
+            json!(if t.side == Side::Buy { "buy" } else { "sell" }), json!(t.quantity), json!(t.price), json!(t.fee),
+        ]).collect(),
+    }
+}
+
+pub fn positions_table(ledger: &Ledger) -> Result<Table, String> {
+    let replay = ledger.replay()?;
+    Ok(Table {
+        name: "ledger",
+        headers: vec!["class", "symbol", "quantity", "avg_cost_idr", "cost_idr", "realized_idr", "fees_idr", "since"],
+        rows: replay.positions.iter().map(|p| vec![
+            json!(p.class.code()), json!(p.symbol), json!(p.quantity), json!(p.avg_cost()), json!(p.cost),
+            json!(p.realized), json!(p.fees), json!(p.since.map(|d| d.to_string())),
+        ]).collect(),
+    })
+}
+
+pub fn watchlist_table(symbols: &[String]) -> Table {
+    Table { name: "watchlist", headers: vec!["symbol"], rows: symbols.iter().map(|s| vec![json!(s)]).collect() }
+}
+
+// ==========================================
+// IMPORT PRESETS
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset { Lubix, IdxBroker, CryptoExchange }
+
+// Column aliases are matched after lowercasing and dropping everything but letters and digits,
+// so "Harga (IDR)" matches "hargaidr". Broker formats change over time; the aliases are deliberately broad.
+struct Columns {
+    date: &'static [&'static str],
+    symbol: &'static [&'static str],
+    side: &'static [&'static str],
+    quantity: &'static [&'static str],
+    price: &'static [&'static str],
+    fee: &'static [&'static str],
+    class: &'static [&'static str],
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Lubix, Preset::IdxBroker, Preset::CryptoExchange];
+
+    pub fn code(&self) -> &'static str {
+        match self { Preset::Lubix => "lubix", Preset::IdxBroker => "idx", Preset::CryptoExchange => "kripto" }
+    }
+
+    // Broker and exchange names map onto the family whose export layout they share.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "stockbit" | "ajaib" | "ipot" | "indopremier" | "mirae" | "bions" | "most" => Some(Preset::IdxBroker),
+            "indodax" | "pintu" | "tokocrypto" | "reku" | "crypto" => Some(Preset::CryptoExchange),
+            other => Self::ALL.into_iter().find(|p| p.code() == other),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Preset::Lubix => "Lubix (hasil /export)",
+            Preset::IdxBroker => "Broker saham IDX (Stockbit, Ajaib, IPOT, Mirae, BIONS)",
+            Preset::CryptoExchange => "Exchange kripto (Indodax, Pintu, Tokocrypto, Reku)",
+        }
+    }
+
+    // Asset class when the file has no class column.
+    fn class(&self) -> AssetClass {
+        match self { Preset::CryptoExchange => AssetClass::Crypto, _ => AssetClass::Stock }
+    }
+
+    // IDX brokers report quantity in lots of 100 shares.
+    fn lot_size(&self) -> f64 {
+        match self { Preset::IdxBroker => IDX_LOT, _ => 1.0 }
+    }
+
+    // IDR-first exports use "1.500.000,50"; crypto exchanges and our own export use "1500000.50".
+    fn thousands_dot(&self) -> bool {
+        *self == Preset::IdxBroker
+    }
+
+    fn columns(&self) -> Columns {
+        match self {
+            Preset::Lubix => Columns {
+                date: &["date"], symbol: &["symbol"], side: &["side"], quantity: &["quantity"],
+                price: &["priceidr", "price"], fee: &["feeidr", "fee"], class: &["class"],
+            },
+            Preset::IdxBroker => Columns {
+                date: &["date", "tanggal", "tradedate", "tanggaltransaksi", "transactiondate"],
+                symbol: &["stock", "symbol", "kodesaham", "stockcode", "kode", "saham", "ticker"],
+                side: &["action", "type", "tipe", "bs", "side", "jenis", "buysell"],
+                quantity: &["lot", "lots", "jumlahlot", "qtylot"],
+                price: &["price", "harga", "avgprice", "hargarata", "doneprice"],
+                fee: &["fee", "biaya", "totalfee", "commission", "komisi"],
+                class: &[],
+            },
+            Preset::CryptoExchange => Columns {
+                date: &["date", "time", "tanggal", "datetime", "waktu"],
+                symbol: &["pair", "asset", "coin", "symbol", "aset", "market"],
+                side: &["type", "side", "tipe", "action", "jenis"],
+                quantity: &["amount", "quantity", "jumlah", "qty", "volume"],
+                price: &["priceidr", "price", "harga", "hargaidr"],
+                fee: &["feeidr", "fee", "biaya"],
+                class: &[],
+            },
+        }
+    }
+}
+
+fn normalize(header: &str) -> String {
+    header.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+struct Mapping {
+    date: usize,
+    symbol: usize,
+    side: usize,
+    quantity: usize,
+    price: usize,
+    fee: Option<usize>,
+    class: Option<usize>,
+}
+
+fn map_columns(preset: Preset, headers: &[String]) -> Result<Mapping, String> {
+    let normalized: Vec<String> = headers.iter().map(|h| normalize(h)).collect();
+    let find = |aliases: &[&str]| aliases.iter().find_map(|a| normalized.iter().position(|h| h == a));
+    let cols = preset.columns();
+    let required = |aliases: &[&str], name: &str| find(aliases).ok_or_else(|| format!("Kolom {} tidak ditemukan untuk preset {}", name, preset.label()));
+    Ok(Mapping {
+        date: required(cols.date, "tanggal")?,
+        symbol: required(cols.symbol, "simbol")?,
+        side: required(cols.side, "beli/jual")?,
+        quantity: required(cols.quantity, "jumlah")?,
+        price: required(cols.price, "harga")?,
+        fee: find(cols.fee),
+        // Our own export is only recognised with its class column, so generic exchange files fall through.
+        class: if preset == Preset::Lubix { Some(required(cols.class, "kelas")?) } else { find(cols.class) },
+    })
+}
+
+// First preset whose required columns are all present.
+pub fn detect_preset(headers: &[String]) -> Option<Preset> {
+    Preset::ALL.into_iter().find(|p| map_columns(*p, headers).is_ok())
+}
+
+// ==========================================
+// IMPORT ROWS
+// ==========================================
+
+fn parse_number(raw: &str, thousands_dot: bool) -> Result<f64, String> {
+    let cleaned: String = raw.trim().chars().filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-')).collect();
+    let cleaned = if thousands_dot { cleaned.replace('.', "").replace(',', ".") } else { decimal_comma(&cleaned)? };
+    cleaned.parse::<f64>().map(f64::abs).map_err(|_| format!("angka tidak valid \"{}\"", raw.trim()))
+}
+
+// Dot-decimal exports still contain "0,5" from spreadsheets, so a lone comma is a decimal mark.
+// "1,500" could be 1.5 or 1500 and is rejected, as /ledger does for "1.500".
+fn decimal_comma(s: &str) -> Result<String, String> {
+    match (s.rfind('.'), s.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => Ok(s.replace('.', "").replace(',', ".")),
+        (None, Some(comma)) if s.matches(',').count() == 1 => {
+            let int = s[..comma].trim_start_matches('-').trim_start_matches('0');
+            if s.len() - comma - 1 == 3 && !int.is_empty() {
+                Err(format!("angka ambigu \"{}\" (tulis {} atau {}.{})", s, s.replace(',', ""), int, &s[comma + 1..]))
+            } else {
+                Ok(s.replace(',', "."))
+            }
+        }
+        _ => Ok(s.replace(',', "")),
+    }
+}
+
+fn parse_date(raw: &str) -> Result<NaiveDate, String> {
+    let raw = raw.trim();
+    const DATES: [&str; 5] = ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y", "%Y/%m/%d", "%d %b %Y"];
+    const DATETIMES: [&str; 4] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M"];
+    DATES.iter().find_map(|f| NaiveDate::parse_from_str(raw, f).ok())
+        .or_else(|| DATETIMES.iter().find_map(|f| NaiveDateTime::parse_from_str(raw, f).ok().map(|d| d.date())))
+        .ok_or_else(|| format!("tanggal tidak dikenali \"{}\"", raw))
+}
+
+fn parse_side(raw: &str) -> Result<Side, String> {
+    match raw.trim().to_lowercase().as_str() {
+        "buy" | "beli" | "b" | "bid" => Ok(Side::Buy),
+        "sell" | "jual" | "s" | "ask" => Ok(Side::Sell),
+        other => Err(format!("jenis transaksi tidak dikenali \"{}\"", other)),
+    }
+}
+
+// "btc_idr", "BTC/IDR", "BTC-IDR" -> "BTC". Prices are stored in IDR, so other quote currencies are rejected.
+fn base_symbol(raw: &str) -> Result<String, String> {
+    let mut parts = raw.trim().split(['_', '/', '-']);
+    let base = parts.next().unwrap_or("").to_uppercase();
+    match parts.next().map(str::to_uppercase) {
+        Some(quote) if quote != "IDR" => Err(format!("pair {} tidak dalam IDR, harga tidak bisa dicatat", raw.trim())),
+        _ => Ok(base),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub preset: Option<Preset>,
+    pub rows: usize,
+    pub trades: Vec<(usize, Trade)>,
+    pub errors: Vec<String>,
+}
+
+// Rows are parsed independently; each accepted trade must also keep `ledger` consistent
+// (no sell larger than the holding at that date), checked in file order on a scratch copy.
+pub fn import_trades(text: &str, preset: Option<Preset>, ledger: &Ledger) -> Result<ImportReport, String> {
+    let rows = parse_csv(text, detect_delimiter(text));
+    let (headers, body) = rows.split_first().ok_or_else(|| "File CSV kosong".to_string())?;
+    let preset = match preset {
+        Some(p) => p,
+        None => detect_preset(headers).ok_or_else(|| "Format CSV tidak dikenali, pilih preset broker".to_string())?,
+    };
+    let mapping = map_columns(preset, headers)?;
+    if body.len() > MAX_IMPORT_ROWS {
+        return Err(format!("Maksimal {} baris per impor", MAX_IMPORT_ROWS));
+    }
+
+    let mut scratch = ledger.clone();
+    let mut report = ImportReport { preset: Some(preset), rows: body.len(), ..Default::default() };
+    for (i, row) in body.iter().enumerate() {
+        // Row numbers as shown in a spreadsheet: the header is row 1.
+        let line = i + 2;
+        let cell = |idx: usize| row.get(idx).map(String::as_str).unwrap_or("");
+        let parsed = (|| -> Result<Trade, String> {
+            let class = match mapping.class {
+                Some(idx) => AssetClass::parse(cell(idx)).ok_or_else(|| format!("kelas aset tidak dikenal \"{}\"", cell(idx)))?,
+                None => preset.class(),
+            };
+            let symbol = match class {
+                AssetClass::Token => cell(mapping.symbol).trim().to_string(),
+                AssetClass::Cash => "IDR".to_string(),
+                _ => base_symbol(cell(mapping.symbol))?,
+            };
+            if symbol.is_empty() {
+                return Err("simbol kosong".to_string());
+            }
+            let quantity = parse_number(cell(mapping.quantity), preset.thousands_dot())? * preset.lot_size();
+            if quantity <= 0.0 {
+                return Err("jumlah harus lebih dari 0".to_string());
+            }
+            let fee = match mapping.fee {
+                Some(idx) if !cell(idx).trim().is_empty() => parse_number(cell(idx), preset.thousands_dot())?,
+                _ => 0.0,
+            };
+            Ok(Trade {
+                date: parse_date(cell(mapping.date))?,
+                class,
+                symbol,
+                side: parse_side(cell(mapping.side))?,
+                quantity,
+                price: if class == AssetClass::Cash { 1.0 } else { parse_number(cell(mapping.price), preset.thousands_dot())? },
+                fee,
+            })
+        })();
+        let result = parsed.and_then(|trade| {
+            scratch.trades.push(trade.clone());
+            match scratch.replay() {
+                Ok(_) => Ok(trade),
+                Err(e) => { scratch.trades.pop(); Err(e) }
+            }
+        });
+        match result {
+            Ok(trade) => report.trades.push((line, trade)),
+            Err(e) => report.errors.push(format!("Baris {}: {}", line, e)),
+        }
+    }
+    Ok(report)
+}
+
+pub fn format_import_report(report: &ImportReport) -> String {
+    let mut out = format!(
+        "📥 <b>IMPOR TRANSAKSI</b>\n========================\n• Preset: <code>{}</code>\n• Total baris: <code>{}</code>\n• Valid: <code>{}</code>\n• Error: <code>{}</code>\n",
+        report.preset.map(|p| p.label()).unwrap_or("-"), report.rows, report.trades.len(), report.errors.len()
+    );
+    if !report.trades.is_empty() {
+        out.push_str("\n✅ <b>CONTOH:</b>\n");
+        for (line, t) in report.trades.iter().take(5) {
+            out.push_str(&format!(
+                "• #{} <code>{}</code> {} {} <b>{}</b> {} @ Rp {}\n",
+                line, t.date, if t.side == Side::Buy { "🟢" } else { "🔴" }, t.class.emoji(),
+                escape_html(&t.symbol), t.quantity, crate::api::format_number(t.price)
+            ));
+        }
+    }
+    if !report.errors.is_empty() {
+        out.push_str("\n❌ <b>ERROR PER BARIS:</b>\n");
+        for e in report.errors.iter().take(MAX_REPORTED_ERRORS) {
+            out.push_str(&format!("• {}\n", escape_html(e)));
+        }
+        if report.errors.len() > MAX_REPORTED_ERRORS {
+            out.push_str(&format!("<i>+{} error lainnya</i>\n", report.errors.len() - MAX_REPORTED_ERRORS));
+        }
+    }
+    out.push_str("========================\n<i>💡 Harga & fee dibaca dalam Rupiah • Saham broker dalam lot (×100)</i>");
+    out
+}
+
+pub fn format_presets() -> String {
+    let list: Vec<String> = Preset::ALL.iter().map(|p| format!("• <code>{}</code> - {}", p.code(), p.label())).collect();
+    format!(
+        "📥 <b>IMPOR CSV</b>\n\nKirim file CSV riwayat transaksi dari broker/exchange.\nPreset dipilih otomatis dari nama kolom, atau tulis nama broker di caption (contoh: <code>stockbit</code>, <code>indodax</code>).\n\n{}\n\n<i>Kolom wajib: tanggal, simbol, beli/jual, jumlah, harga. Fee opsional.</i>",
+        list.join("\n")
+    )
+}
+
+pub fn apply_import(ledger: &mut Ledger, trades: &[(usize, Trade)]) -> (usize, Vec<String>) {
+    let mut added = 0;
+    let mut errors = Vec::new();
+    for (line, trade) in trades {
+        ledger.trades.push(trade.clone());
+        if let Err(e) = ledger.replay() {
+            ledger.trades.pop();
+            errors.push(format!("Baris {}: {}", line, e));
+        } else {
+            added += 1;
+        }
+    }
+    (added, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|h| h.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_reader_handles_quotes_crlf_and_bom() {
+        let text = "\u{feff}date,symbol,note\r\n2025-01-02,BBRI,\"beli, \"\"lot\"\" pertama\"\r\n\r\n2025-01-03,TLKM,\"dua\nbaris\"";
+        let rows = parse_csv(text, ',');
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], headers(&["date", "symbol", "note"]));
+        assert_eq!(rows[1][2], "beli, \"lot\" pertama");
+        assert_eq!(rows[2][2], "dua\nbaris");
+        assert_eq!(detect_delimiter("Tanggal;Kode;Lot\n2025-01-02;BBRI;1,5"), ';');
+    }
+
+    #[test]
+    fn lone_comma_is_decimal_unless_ambiguous() {
+        assert_eq!(parse_number("0,5", false).unwrap(), 0.5);
+        assert_eq!(parse_number("12,25", false).unwrap(), 12.25);
+        assert_eq!(parse_number("1,500,000", false).unwrap(), 1_500_000.0);
+        assert_eq!(parse_number("1,500.25", false).unwrap(), 1500.25);
+        assert_eq!(parse_number("1.500,25", false).unwrap(), 1500.25);
+        assert_eq!(parse_number("0,125", false).unwrap(), 0.125);
+        assert!(parse_number("1,500", false).is_err());
+        assert_eq!(parse_number("Rp 1.500.000,50", true).unwrap(), 1_500_000.5);
+    }
+
+    #[test]
+    fn presets_are_detected_from_headers() {
+        assert_eq!(detect_preset(&headers(&["date", "class", "symbol", "side", "quantity", "price_idr", "fee_idr"])), Some(Preset::Lubix));
+        assert_eq!(detect_preset(&headers(&["Tanggal", "Kode Saham", "B/S", "Lot", "Harga", "Fee"])), Some(Preset::IdxBroker));
+        // Without the class column our own layout is read as a generic exchange file.
+        assert_eq!(detect_preset(&headers(&["date", "symbol", "side", "quantity", "price"])), Some(Preset::CryptoExchange));
+        assert_eq!(detect_preset(&headers(&["date", "symbol", "side"])), None);
+    }
+
+    #[test]
+    fn ambiguous_and_non_idr_rows_are_reported_per_row() {
+        let text = "Date,Pair,Type,Amount,Price\n2025-01-02,btc_idr,buy,\"0,5\",1500000000\n2025-01-03,eth_idr,buy,\"1,500\",50000000\n2025-01-04,btc_usdt,buy,1,95000\n";
+        let report = import_trades(text, Some(Preset::CryptoExchange), &Ledger::default()).unwrap();
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.trades[0].1.quantity, 0.5);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].starts_with("Baris 3: angka ambigu"));
+        assert!(report.errors[1].contains("tidak dalam IDR"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::api::{self, escape_html, format_compact, DexPairData};
+use crate::solana;
+use chrono::Utc;
+use std::collections::HashMap;
+
+// ==========================================
+// FEED FILTER
+// ==========================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeedFilter {
+    pub min_liquidity: f64,
+    pub min_volume: f64,
+    pub max_age_hours: f64,
+    pub max_risk: u32,
+    pub max_items: usize,
+}
+
+impl FeedFilter {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: f64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            min_liquidity: read("FEED_MIN_LIQUIDITY", 20_000.0),
+            min_volume: read("FEED_MIN_VOLUME", 50_000.0),
+            max_age_hours: read("FEED_MAX_AGE_HOURS", 72.0),
+            max_risk: read("FEED_MAX_RISK", 50.0) as u32,
+            max_items: read("FEED_MAX_ITEMS", 8.0) as usize,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "• Likuiditas ≥ <code>${}</code>\n• Volume 24H ≥ <code>${}</code>\n• Umur pair ≤ <code>{} jam</code>\n• Risk score ≤ <code>{}</code>",
+            format_compact(self.min_liquidity), format_compact(self.min_volume), self.max_age_hours, self.max_risk
+        )
+    }
+
+    fn passes_market(&self, pair: &DexPairData, now_ms: i64) -> bool {
+        let liquidity = pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+        let volume = pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0);
+        let fresh = pair.pair_created_at.is_some_and(|t| (now_ms - t) as f64 / 3_600_000.0 <= self.max_age_hours);
+        liquidity >= self.min_liquidity && volume >= self.min_volume && fresh
+    }
+}
+
+// ==========================================
+// DIGEST
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    pub pair: DexPairData,
+    pub trending: bool,
+    pub risk: u32,
+    pub risk_label: &'static str,
+}
+
+async fn fetch_candidates() -> Result<HashMap<String, bool>, String> {
+    let (trending, latest) = tokio::join!(
+        api::fetch_dex_token_refs("token-boosts/top/v1"),
+        api::fetch_dex_token_refs("token-profiles/latest/v1")
+    );
+    if trending.is_err() && latest.is_err() {
+        return Err("DexScreener tidak merespons".to_string());
+    }
+    let mut out: HashMap<String, bool> = HashMap::new();
+    for t in latest.unwrap_or_default().into_iter().filter(|t| t.chain_id == "solana") {
+        out.entry(t.token_address).or_insert(false);
+    }
+    for t in trending.unwrap_or_default().into_iter().filter(|t| t.chain_id == "solana") {
+        out.insert(t.token_address, true);
+    }
+    Ok(out)
+}
+
+// Pairs already in `seen` (pair address → announce time) are skipped; the caller marks the returned pairs as announced.
+pub async fn build_digest(filter: &FeedFilter, seen: &HashMap<String, i64>) -> Result<Vec<FeedItem>, String> {
+    let candidates = fetch_candidates().await?;
+    let mints: Vec<String> = candidates.keys().cloned().collect();
+    let now_ms = Utc::now().timestamp_millis();
+
+    // One pair per token: the most liquid Solana pool.
+    let mut best: HashMap<String, DexPairData> = HashMap::new();
+    for pair in api::fetch_token_pairs(&mints).await.into_iter().filter(|p| p.chain_id == "solana") {
+        let liquidity = pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+        let current = best.get(&pair.base_token.address).and_then(|p| p.liquidity.as_ref()).and_then(|l| l.usd).unwrap_or(-1.0);
+        if candidates.contains_key(&pair.base_token.address) && liquidity > current {
+            best.insert(pair.base_token.address.clone(), pair);
+        }
+    }
+
+    let mut pairs: Vec<DexPairData> = best.into_values()
+        .filter(|p| !seen.contains_key(&p.pair_address) && filter.passes_market(p, now_ms))
+        .collect();
+    pairs.sort_by(|a, b| {
+        let vol = |p: &DexPairData| p.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0);
+        vol(b).partial_cmp(&vol(a)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // The safety check costs two RPC calls per token, so only the strongest candidates are checked.
+    let mut items = Vec::new();
+    for pair in pairs.into_iter().take(filter.max_items * 2) {
+        let Ok(report) = solana::check_token_safety(&pair.base_token.address, Some(&pair)).await else { continue };
+        if report.risk_score() > filter.max_risk { continue; }
+        items.push(FeedItem {
+            trending: candidates.get(&pair.base_token.address).copied().unwrap_or(false),
+            risk: report.risk_score(),
+            risk_label: report.risk_label(),
+            pair,
+        });
+        if items.len() >= filter.max_items { break; }
+    }
+    Ok(items)
+}
+
+pub fn format_digest(items: &[FeedItem]) -> String {
+    let now_ms = Utc::now().timestamp_millis();
+    let mut out = format!("📡 <b>SOLANA PAIR FEED</b> ({})\n========================\n", items.len());
+    if items.is_empty() {
+        out.push_str("<i>Tidak ada pair baru yang lolos filter</i>\n");
+    }
+    for (i, item) in items.iter().enumerate() {
+        let p = &item.pair;
+        let age = p.pair_created_at.map(|t| (now_ms - t).max(0) as f64 / 3_600_000.0).unwrap_or(0.0);
+        out.push_str(&format!(
+            "\n{}. {} <b>{}</b> - {}\n• Price: <code>${}</code> • 24H: <code>{:+.2}%</code>\n• Liq: <code>${}</code> • Vol: <code>${}</code> • Umur: <code>{}</code>\n• Risk: {} <code>{}/100</code>\n• CA: <code>{}</code>\n",
+            i + 1, if item.trending { "🔥" } else { "🆕" },
+            escape_html(&p.base_token.symbol), escape_html(&p.base_token.name),
+            p.price_usd.as_deref().unwrap_or("0"),
+            p.price_change.as_ref().and_then(|c| c.h24).unwrap_or(0.0),
+            format_compact(p.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0)),
+            format_compact(p.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0)),
+            if age < 48.0 { format!("{:.0} jam", age) } else { format!("{:.0} hari", age / 24.0) },
+            item.risk_label, item.risk,
+            p.base_token.address
+        ));
+    }
+    out.push_str("\n========================\n<i>🔥 Trending • 🆕 Baru listing • ⚠️ DYOR, bukan ajakan membeli</i>");
+    out
+}
@@ -0,0 +1,224 @@
+use crate::api::{escape_html, format_compact};
+use crate::solana::{self, short_address};
+use crate::txdecode;
+use serde::Deserialize;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::state::Account as TokenAccount;
+use std::collections::HashMap;
+
+const TOP_HOLDERS: usize = 10;
+const PIE_SLICES: usize = 5;
+
+// ==========================================
+// ADDRESS LABELS
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelKind { Exchange, Burn, Lp, Team, Other }
+
+impl LabelKind {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            LabelKind::Exchange => "🏦",
+            LabelKind::Burn => "🔥",
+            LabelKind::Lp => "💧",
+            LabelKind::Team => "👥",
+            LabelKind::Other => "🏷",
+        }
+    }
+
+    // Supply held here is not free float.
+    pub fn locked(&self) -> bool {
+        matches!(self, LabelKind::Burn | LabelKind::Lp)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressLabel {
+    pub address: String,
+    pub kind: LabelKind,
+    pub name: String,
+}
+
+const DEFAULT_LABELS: [(&str, LabelKind, &str); 8] = [
+    ("1nc1nerator11111111111111111111111111111111", LabelKind::Burn, "Incinerator"),
+    ("11111111111111111111111111111111", LabelKind::Burn, "System Program"),
+    ("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1", LabelKind::Lp, "Raydium AMM Authority"),
+    ("GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL", LabelKind::Lp, "Raydium CPMM Authority"),
+    ("5tzFkiKscXHK5ZXCGbXZxdw7gTjjD1mBwuoFbhUvuAi9", LabelKind::Exchange, "Binance"),
+    ("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM", LabelKind::Exchange, "Binance"),
+    ("H8sMJSCQxfKiFTCfDR3DUMLPwcRbM61LGFJ8N4dK3WjS", LabelKind::Exchange, "Coinbase"),
+    ("AC5RDfQFmDS1deWZos921JfqscXdByf8BKHs5ACWjtW2", LabelKind::Exchange, "Bybit"),
+];
+
+// Built-in labels plus the JSON list at HOLDER_LABELS_PATH ([{"address", "kind", "name"}]); file entries win.
+pub fn load_labels() -> HashMap<String, AddressLabel> {
+    let mut labels: HashMap<String, AddressLabel> = DEFAULT_LABELS.iter()
+        .map(|(address, kind, name)| (address.to_string(), AddressLabel { address: address.to_string(), kind: *kind, name: name.to_string() }))
+        .collect();
+    let Ok(path) = std::env::var("HOLDER_LABELS_PATH") else { return labels };
+    match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|raw| serde_json::from_str::<Vec<AddressLabel>>(&raw).map_err(|e| e.to_string())) {
+        Ok(extra) => labels.extend(extra.into_iter().map(|l| (l.address.clone(), l))),
+        Err(e) => log::warn!("Failed to load holder labels from {}: {}", path, e),
+    }
+    labels
+}
+
+// ==========================================
+// HOLDER REPORT
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct Holder {
+    pub token_account: String,
+    pub owner: Option<String>,
+    pub amount: f64,
+    pub share: f64,
+    pub label: Option<(LabelKind, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HolderReport {
+    pub mint: String,
+    pub supply: f64,
+    pub holders: Vec<Holder>,
+}
+
+impl HolderReport {
+    pub fn top10_share(&self) -> f64 {
+        self.holders.iter().take(TOP_HOLDERS).map(|h| h.share).sum()
+    }
+
+    // Top-10 share ignoring burn and LP accounts, i.e. what wallets can actually sell.
+    pub fn top10_free_share(&self) -> f64 {
+        self.holders.iter()
+            .filter(|h| !h.label.as_ref().is_some_and(|l| l.0.locked()))
+            .take(TOP_HOLDERS)
+            .map(|h| h.share)
+            .sum()
+    }
+
+    // Over the largest accounts only (RPC returns at most 20), so it measures concentration among whales.
+    pub fn gini(&self) -> f64 {
+        let mut amounts: Vec<f64> = self.holders.iter().map(|h| h.amount).filter(|a| *a > 0.0).collect();
+        let n = amounts.len() as f64;
+        let total: f64 = amounts.iter().sum();
+        if amounts.len() < 2 || total <= 0.0 { return 0.0; }
+        amounts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let weighted: f64 = amounts.iter().enumerate().map(|(i, a)| (i as f64 + 1.0) * a).sum();
+        2.0 * weighted / (n * total) - (n + 1.0) / n
+    }
+
+    // Top holders followed by "the rest", in supply percent.
+    pub fn pie_slices(&self) -> Vec<(String, f64)> {
+        let mut slices: Vec<(String, f64)> = self.holders.iter().take(PIE_SLICES)
+            .map(|h| (holder_name(h), h.share))
+            .collect();
+        let rest = 100.0 - slices.iter().map(|s| s.1).sum::<f64>();
+        if rest > 0.0 {
+            slices.push(("Lainnya".to_string(), rest));
+        }
+        slices
+    }
+}
+
+fn holder_name(h: &Holder) -> String {
+    match (&h.label, &h.owner) {
+        (Some((_, name)), _) => name.clone(),
+        (None, Some(owner)) => short_address(owner),
+        (None, None) => short_address(&h.token_account),
+    }
+}
+
+pub async fn fetch_holder_report(mint: &str) -> Result<HolderReport, String> {
+    let mint_key = solana::parse_pubkey(mint)?;
+    let client = solana::rpc_client();
+    let info = solana::fetch_mint_info(&client, &mint_key).await?;
+    let largest = client.get_token_largest_accounts(&mint_key).await.map_err(|e| format!("RPC error: {}", e))?;
+    let labels = load_labels();
+
+    // Largest accounts are token accounts; their owner is what labels and DEX detection key on.
+    let account_keys: Vec<Pubkey> = largest.iter().filter_map(|a| solana::parse_pubkey(&a.address).ok()).collect();
+    let accounts = client.get_multiple_accounts(&account_keys).await.map_err(|e| format!("RPC error: {}", e))?;
+    let owners: Vec<Option<Pubkey>> = accounts.iter()
+        .map(|a| a.as_ref()
+            .filter(|a| a.data.len() >= TokenAccount::LEN)
+            .and_then(|a| TokenAccount::unpack_from_slice(&a.data[..TokenAccount::LEN]).ok())
+            .map(|t| t.owner))
+        .collect();
+
+    // Owners that are program accounts (pool vaults, bonding curves) reveal the DEX holding the tokens.
+    let owner_keys: Vec<Pubkey> = owners.iter().flatten().copied().collect();
+    let owner_programs: HashMap<Pubkey, Pubkey> = client.get_multiple_accounts(&owner_keys).await
+        .map(|accs| owner_keys.iter().zip(accs).filter_map(|(k, a)| a.map(|a| (*k, a.owner))).collect())
+        .unwrap_or_default();
+
+    let supply = info.ui_supply();
+    let holders = largest.iter().zip(owners).map(|(account, owner)| {
+        let amount = account.amount.ui_amount.unwrap_or(0.0);
+        let label = owner.as_ref().and_then(|o| labels.get(&o.to_string()))
+            .or_else(|| labels.get(&account.address))
+            .map(|l| (l.kind, l.name.clone()))
+            .or_else(|| {
+                let program = owner_programs.get(owner.as_ref()?)?;
+                match txdecode::program_label(&program.to_string()) {
+                    Some((name, true)) => Some((LabelKind::Lp, name.to_string())),
+                    _ => None,
+                }
+            });
+        Holder {
+            token_account: account.address.clone(),
+            owner: owner.map(|o| o.to_string()),
+            amount,
+            share: if supply > 0.0 { amount / supply * 100.0 } else { 0.0 },
+            label,
+        }
+    }).collect();
+
+    Ok(HolderReport { mint: mint_key.to_string(), supply, holders })
+}
+
+pub fn format_holder_report(report: &HolderReport, token: Option<&(String, f64)>) -> String {
+    let symbol = token.map(|t| t.0.clone()).unwrap_or_else(|| short_address(&report.mint));
+    let gini = report.gini();
+    let concentration = match report.top10_free_share() {
+        s if s >= 50.0 => "🔴 Sangat terkonsentrasi",
+        s if s >= 25.0 => "🟡 Terkonsentrasi",
+        _ => "🟢 Tersebar",
+    };
+    let mut out = format!(
+        "👥 <b>HOLDER DISTRIBUTION - {}</b>\n========================\n• Supply: <code>{}</code>\n• Top 10: <code>{:.2}%</code>\n• Top 10 (tanpa LP/burn): <code>{:.2}%</code>\n• Gini (top {}): <code>{:.3}</code>\n• Status: {}\n\n",
+        escape_html(&symbol), format_compact(report.supply), report.top10_share(), report.top10_free_share(),
+        report.holders.len(), gini, concentration
+    );
+
+    out.push_str("<pre>#  Holder          Share   Jumlah\n");
+    for (i, h) in report.holders.iter().take(TOP_HOLDERS).enumerate() {
+        let name: String = holder_name(h).chars().take(15).collect();
+        out.push_str(&format!(
+            "{:<2} {} {:>6.2}% {:>8}\n",
+            i + 1, escape_html(&format!("{:<15}", name)), h.share, format_compact(h.amount)
+        ));
+    }
+    out.push_str("</pre>\n");
+
+    let labelled: Vec<&Holder> = report.holders.iter().filter(|h| h.label.is_some()).collect();
+    if !labelled.is_empty() {
+        out.push_str("🏷 <b>LABEL:</b>\n");
+        for h in labelled {
+            if let Some((kind, name)) = &h.label {
+                let value = token.map(|t| format!(" ≈ <code>${}</code>", format_compact(t.1 * h.amount))).unwrap_or_default();
+                out.push_str(&format!("• {} {}: <code>{:.2}%</code>{}\n", kind.emoji(), escape_html(name), h.share, value));
+            }
+        }
+        out.push('\n');
+    }
+
+    out.push_str(&format!(
+        "🔗 <b>CA:</b> <code>{}</code>\n========================\n<i>💡 🏦 Exchange • 🔥 Burn • 💧 LP/DEX • Gini 0 = merata, 1 = satu whale</i>",
+        report.mint
+    ));
+    out
+}
@@ -0,0 +1,539 @@
+use crate::api::{escape_html, format_number};
+use crate::solana::short_address;
+use chrono::{NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+// ==========================================
+// REAL PORTFOLIO LEDGER
+// ==========================================
+
+// All prices and fees are recorded in IDR, regardless of the asset's quote currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AssetClass { Stock, Crypto, Token, Cash }
+
+impl AssetClass {
+    pub const ALL: [AssetClass; 4] = [AssetClass::Stock, AssetClass::Crypto, AssetClass::Token, AssetClass::Cash];
+
+    pub fn code(&self) -> &'static str {
+        match self { AssetClass::Stock => "saham", AssetClass::Crypto => "kripto", AssetClass::Token => "token", AssetClass::Cash => "kas" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "stock" => Some(AssetClass::Stock),
+            "crypto" => Some(AssetClass::Crypto),
+            "spl" => Some(AssetClass::Token),
+            "cash" => Some(AssetClass::Cash),
+            _ => Self::ALL.into_iter().find(|c| c.code() == s),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { AssetClass::Stock => "🕌 Saham IDX", AssetClass::Crypto => "🪙 Kripto", AssetClass::Token => "⚡️ Token Solana", AssetClass::Cash => "💵 Kas IDR" }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self { AssetClass::Stock => "🕌", AssetClass::Crypto => "🪙", AssetClass::Token => "⚡️", AssetClass::Cash => "💵" }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side { Buy, Sell }
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CostMethod { #[default] Fifo, Average }
+
+impl CostMethod {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "fifo" => Some(CostMethod::Fifo),
+            "avg" | "average" | "rata" => Some(CostMethod::Average),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { CostMethod::Fifo => "FIFO", CostMethod::Average => "Average" }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trade {
+    pub date: NaiveDate,
+    pub class: AssetClass,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    pub fee: f64,
+}
+
+impl Trade {
+    pub fn gross(&self) -> f64 {
+        self.quantity * self.price
+    }
+
+    pub fn key(&self) -> String {
+        position_key(self.class, &self.symbol)
+    }
+}
+
+pub fn position_key(class: AssetClass, symbol: &str) -> String {
+    format!("{}:{}", class.code(), symbol)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    pub method: CostMethod,
+    pub trades: Vec<Trade>,
+}
+
+// ==========================================
+// TRADE ENTRY
+// ==========================================
+
+// "1.500.000" and "0,5" follow Indonesian notation; a single dot stays a decimal point ("0.01").
+// "4.500" could be either, so it is rejected instead of silently recorded as 4.5.
+pub fn parse_number(v: &str) -> Result<f64, String> {
+    if let Some((int, frac)) = v.split_once('.') {
+        let int = int.trim_start_matches('0');
+        if !v.contains(',') && !frac.contains('.') && frac.len() == 3 && !int.is_empty() {
+            return Err(format!("Angka ambigu: {} (tulis {} atau {},{})", v, v.replace('.', ""), int, frac));
+        }
+    }
+    let cleaned = if v.contains(',') { v.replace('.', "").replace(',', ".") } else if v.matches('.').count() > 1 { v.replace('.', "") } else { v.to_string() };
+    cleaned.parse::<f64>().ok().filter(|n| *n >= 0.0).ok_or_else(|| format!("Angka tidak valid: {}", v))
+}
+
+// "beli saham BBRI 1000 4500 fee=6750 2025-01-10", "jual kripto BTC 0.01 1500000000",
+// "beli kas 25000000 2025-01-02". Token symbols are mint addresses and keep their case.
+pub fn parse_trade(text: &str) -> Result<Trade, String> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let side = match parts.first().map(|s| s.to_lowercase()).as_deref() {
+        Some("beli" | "buy" | "setor") => Side::Buy,
+        Some("jual" | "sell" | "tarik") => Side::Sell,
+        _ => return Err("Awali dengan beli/jual".to_string()),
+    };
+    let class = parts.get(1).and_then(|c| AssetClass::parse(c)).ok_or_else(|| "Kelas aset: saham, kripto, token, kas".to_string())?;
+    let (symbol, quantity, price, rest) = if class == AssetClass::Cash {
+        let amount = parts.get(2).ok_or_else(|| "Format: beli kas JUMLAH".to_string())?;
+        ("IDR".to_string(), parse_number(amount)?, 1.0, &parts[3..])
+    } else {
+        let [symbol, qty, price, ..] = parts.get(2..).unwrap_or_default() else {
+            return Err("Format: beli KELAS SIMBOL JUMLAH HARGA [fee=X] [YYYY-MM-DD]".to_string());
+        };
+        let symbol = if class == AssetClass::Token { symbol.to_string() } else { symbol.to_uppercase() };
+        (symbol, parse_number(qty)?, parse_number(price)?, &parts[5..])
+    };
+    if quantity <= 0.0 {
+        return Err("Jumlah harus lebih dari 0".to_string());
+    }
+
+    let mut fee = 0.0;
+    let mut date = Utc::now().date_naive();
+    for token in rest {
+        if let Some(v) = token.strip_prefix("fee=") {
+            fee = parse_number(v)?;
+        } else {
+            date = NaiveDate::parse_from_str(token, "%Y-%m-%d").map_err(|_| format!("Parameter tidak dikenal: {}", token))?;
+        }
+    }
+    if date > Utc::now().date_naive() {
+        return Err("Tanggal tidak boleh di masa depan".to_string());
+    }
+    Ok(Trade { date, class, symbol, side, quantity, price, fee })
+}
+
+// ==========================================
+// POSITIONS
+// ==========================================
+
+#[derive(Debug, Clone)]
+struct Lot {
+    quantity: f64,
+    unit_cost: f64,
+    date: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub class: AssetClass,
+    pub symbol: String,
+    pub quantity: f64,
+    pub cost: f64,
+    pub realized: f64,
+    pub fees: f64,
+    // Date of the oldest open lot, used for haul.
+    pub since: Option<NaiveDate>,
+    lots: VecDeque<Lot>,
+}
+
+impl Position {
+    pub fn key(&self) -> String {
+        position_key(self.class, &self.symbol)
+    }
+
+    pub fn avg_cost(&self) -> f64 {
+        if self.quantity > 0.0 { self.cost / self.quantity } else { 0.0 }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.quantity > 1e-9
+    }
+}
+
+// Realized result of one sell; `proceeds` is net of the sell fee.
+#[derive(Debug, Clone)]
+pub struct Disposal {
+    pub date: NaiveDate,
+    pub class: AssetClass,
+    pub symbol: String,
+    pub quantity: f64,
+    pub gross: f64,
+    pub proceeds: f64,
+    pub cost: f64,
+}
+
+impl Disposal {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub positions: Vec<Position>,
+    pub disposals: Vec<Disposal>,
+}
+
+impl Ledger {
+    // Trades sorted by date; same-day trades keep entry order.
+    pub fn sorted_trades(&self) -> Vec<&Trade> {
+        let mut trades: Vec<&Trade> = self.trades.iter().collect();
+        trades.sort_by_key(|t| t.date);
+        trades
+    }
+
+    pub fn replay(&self) -> Result<Replay, String> {
+        let mut positions: BTreeMap<String, Position> = BTreeMap::new();
+        let mut disposals = Vec::new();
+        for t in self.sorted_trades() {
+            let p = positions.entry(t.key()).or_insert_with(|| Position {
+                class: t.class, symbol: t.symbol.clone(), quantity: 0.0, cost: 0.0, realized: 0.0, fees: 0.0, since: None, lots: VecDeque::new(),
+            });
+            p.fees += t.fee;
+            match t.side {
+                Side::Buy => {
+                    let unit_cost = (t.gross() + t.fee) / t.quantity;
+                    p.lots.push_back(Lot { quantity: t.quantity, unit_cost, date: t.date });
+                    p.quantity += t.quantity;
+                    p.cost += t.gross() + t.fee;
+                }
+                Side::Sell => {
+                    if t.quantity > p.quantity + 1e-9 {
+                        return Err(format!("{} {}: jual {} melebihi saldo {} ({})", t.class.code(), t.symbol, t.quantity, p.quantity, t.date));
+                    }
+                    let cost = match self.method {
+                        CostMethod::Average => {
+                            let cost = p.avg_cost() * t.quantity;
+                            consume_lots(&mut p.lots, t.quantity);
+                            cost
+                        }
+                        CostMethod::Fifo => consume_lots(&mut p.lots, t.quantity),
+                    };
+                    let proceeds = t.gross() - t.fee;
+                    p.quantity -= t.quantity;
+                    p.cost = if p.is_open() { (p.cost - cost).max(0.0) } else { 0.0 };
+                    p.realized += proceeds - cost;
+                    disposals.push(Disposal {
+                        date: t.date, class: t.class, symbol: t.symbol.clone(), quantity: t.quantity, gross: t.gross(), proceeds, cost,
+                    });
+                }
+            }
+            p.since = p.lots.front().map(|l| l.date);
+        }
+        Ok(Replay { positions: positions.into_values().collect(), disposals })
+    }
+}
+
+// Removes `quantity` from the oldest lots and returns their cost.
+fn consume_lots(lots: &mut VecDeque<Lot>, mut quantity: f64) -> f64 {
+    let mut cost = 0.0;
+    while quantity > 1e-9 {
+        let Some(lot) = lots.front_mut() else { break };
+        let take = lot.quantity.min(quantity);
+        cost += take * lot.unit_cost;
+        lot.quantity -= take;
+        quantity -= take;
+        if lot.quantity <= 1e-9 { lots.pop_front(); }
+    }
+    cost
+}
+
+// ==========================================
+// VALUATION
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Compliance { Syariah, Doubtful, NonSyariah, Unknown }
+
+impl Compliance {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Compliance::Syariah => "✅ Syariah",
+            Compliance::Doubtful => "⚠️ Syubhat",
+            Compliance::NonSyariah => "❌ Non-syariah",
+            Compliance::Unknown => "❔ Belum diketahui",
+        }
+    }
+}
+
+// Current IDR price and screening status of a position, fetched by the caller.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub name: Option<String>,
+    pub price: Option<f64>,
+    pub compliance: Compliance,
+}
+
+pub struct Valued<'a> {
+    pub position: &'a Position,
+    pub quote: Option<&'a Quote>,
+}
+
+impl Valued<'_> {
+    // Falls back to cost when no live price is available.
+    pub fn value(&self) -> f64 {
+        match self.quote.and_then(|q| q.price) {
+            Some(price) => price * self.position.quantity,
+            None => self.position.cost,
+        }
+    }
+
+    pub fn unrealized(&self) -> Option<f64> {
+        self.quote.and_then(|q| q.price).map(|price| price * self.position.quantity - self.position.cost)
+    }
+
+    pub fn compliance(&self) -> Compliance {
+        self.quote.map(|q| q.compliance).unwrap_or(Compliance::Unknown)
+    }
+
+    pub fn name(&self) -> String {
+        match (self.position.class, self.quote.and_then(|q| q.name.clone())) {
+            (_, Some(name)) => name,
+            (AssetClass::Token, None) => short_address(&self.position.symbol),
+            (_, None) => self.position.symbol.clone(),
+        }
+    }
+}
+
+pub fn value_positions<'a>(replay: &'a Replay, quotes: &'a HashMap<String, Quote>) -> Vec<Valued<'a>> {
+    let mut valued: Vec<Valued> = replay.positions.iter()
+        .filter(|p| p.is_open())
+        .map(|p| Valued { position: p, quote: quotes.get(&p.key()) })
+        .collect();
+    valued.sort_by(|a, b| b.value().partial_cmp(&a.value()).unwrap_or(std::cmp::Ordering::Equal));
+    valued
+}
+
+// ==========================================
+// FORMATTING
+// ==========================================
+
+fn signed_idr(n: f64) -> String {
+    format!("{}Rp {}", if n < 0.0 { "-" } else { "+" }, format_number(n.abs()))
+}
+
+fn allocation_lines<K: Ord>(groups: BTreeMap<K, f64>, total: f64, label: impl Fn(&K) -> &'static str) -> String {
+    let mut out = String::new();
+    let count = groups.len();
+    for (i, (k, v)) in groups.iter().enumerate() {
+        let pct = if total > 0.0 { v / total * 100.0 } else { 0.0 };
+        out.push_str(&format!(
+            "{} {}: <code>{:.1}%</code> • <code>Rp {}</code>\n",
+            if i + 1 == count { "└" } else { "├" }, label(k), pct, format_number(*v)
+        ));
+    }
+    out
+}
+
+pub fn format_portfolio(ledger: &Ledger, replay: &Replay, quotes: &HashMap<String, Quote>) -> String {
+    let valued = value_positions(replay, quotes);
+    let total: f64 = valued.iter().map(|v| v.value()).sum();
+    let invested: f64 = valued.iter().map(|v| v.position.cost).sum();
+    let unrealized: f64 = valued.iter().filter_map(|v| v.unrealized()).sum();
+    let realized: f64 = replay.positions.iter().map(|p| p.realized).sum();
+    let fees: f64 = replay.positions.iter().map(|p| p.fees).sum();
+
+    let mut out = format!(
+        "📒 <b>PORTOFOLIO RIIL</b>\n━━━━━━━━━━━━━━━━━━━━━━━\n├ 💰 Nilai: <code>Rp {}</code>\n├ 🧾 Modal: <code>Rp {}</code>\n├ 📈 Unrealized: <code>{}</code>\n├ ✅ Realized: <code>{}</code>\n├ 💸 Total fee: <code>Rp {}</code>\n└ ⚙️ Metode: <code>{}</code> • {} transaksi\n",
+        format_number(total), format_number(invested), signed_idr(unrealized), signed_idr(realized),
+        format_number(fees), ledger.method.label(), ledger.trades.len()
+    );
+
+    out.push_str(&format!("\n📦 <b>POSISI ({})</b>\n", valued.len()));
+    if valued.is_empty() {
+        out.push_str("└ <i>Belum ada posisi. Catat dengan /ledger beli ...</i>\n");
+    }
+    for (i, v) in valued.iter().enumerate() {
+        let p = v.position;
+        let pnl = match v.unrealized() {
+            Some(u) => {
+                let pct = if p.cost > 0.0 { u / p.cost * 100.0 } else { 0.0 };
+                format!("{} <code>{}</code> ({:+.2}%)", if u >= 0.0 { "📈" } else { "📉" }, signed_idr(u), pct)
+            }
+            None => "<i>harga tidak tersedia</i>".to_string(),
+        };
+        let branch = if i + 1 == valued.len() { "└" } else { "├" };
+        if p.class == AssetClass::Cash {
+            out.push_str(&format!("{} {} <b>Kas</b>: <code>Rp {}</code>\n", branch, p.class.emoji(), format_number(p.quantity)));
+        } else {
+            out.push_str(&format!(
+                "{} {} <b>{}</b>: <code>{}</code> @ <code>Rp {}</code>\n   Nilai <code>Rp {}</code> • {}\n",
+                branch, p.class.emoji(), escape_html(&v.name()), p.quantity, format_number(p.avg_cost()),
+                format_number(v.value()), pnl
+            ));
+        }
+    }
+
+    if total > 0.0 {
+        let mut by_class: BTreeMap<AssetClass, f64> = BTreeMap::new();
+        let mut by_compliance: BTreeMap<Compliance, f64> = BTreeMap::new();
+        for v in &valued {
+            *by_class.entry(v.position.class).or_default() += v.value();
+            *by_compliance.entry(v.compliance()).or_default() += v.value();
+        }
+        out.push_str("\n🧩 <b>ALOKASI KELAS ASET</b>\n");
+        out.push_str(&allocation_lines(by_class, total, |c| c.label()));
+        out.push_str("\n🕌 <b>ALOKASI SYARIAH</b>\n");
+        out.push_str(&allocation_lines(by_compliance, total, |c| c.label()));
+    }
+    out.push_str("━━━━━━━━━━━━━━━━━━━━━━━\n<i>💡 Semua harga dalam IDR • Saham: ISSI • Kripto: daftar kurasi</i>");
+    out
+}
+
+pub fn format_trades(ledger: &Ledger) -> String {
+    let mut out = format!("🧾 <b>RIWAYAT TRANSAKSI</b> ({})\n========================\n", ledger.trades.len());
+    if ledger.trades.is_empty() {
+        out.push_str("<i>Belum ada transaksi</i>\n");
+    }
+    let skip = ledger.trades.len().saturating_sub(20);
+    for (i, t) in ledger.trades.iter().enumerate().skip(skip) {
+        let symbol = if t.class == AssetClass::Token { short_address(&t.symbol) } else { t.symbol.clone() };
+        out.push_str(&format!(
+            "{}. <code>{}</code> {} {} <b>{}</b> {} @ Rp {}{}\n",
+            i + 1, t.date, if t.side == Side::Buy { "🟢" } else { "🔴" }, t.class.emoji(), escape_html(&symbol),
+            t.quantity, format_number(t.price),
+            if t.fee > 0.0 { format!(" • fee Rp {}", format_number(t.fee)) } else { String::new() }
+        ));
+    }
+    out.push_str("========================\n<i>Hapus: /ledger hapus NOMOR</i>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    fn trade(d: u32, side: Side, quantity: f64, price: f64, fee: f64) -> Trade {
+        Trade { date: day(d), class: AssetClass::Stock, symbol: "BBRI".to_string(), side, quantity, price, fee }
+    }
+
+    fn book(method: CostMethod, trades: Vec<Trade>) -> Ledger {
+        Ledger { method, trades }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    fn partial_sells() -> Vec<Trade> {
+        vec![
+            trade(1, Side::Buy, 100.0, 1000.0, 0.0),
+            trade(2, Side::Buy, 100.0, 2000.0, 0.0),
+            trade(3, Side::Sell, 150.0, 3000.0, 0.0),
+            trade(4, Side::Sell, 25.0, 2500.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn fifo_consumes_oldest_lots_across_partial_sells() {
+        let replay = book(CostMethod::Fifo, partial_sells()).replay().unwrap();
+        let costs: Vec<f64> = replay.disposals.iter().map(|d| d.cost).collect();
+        assert!(close(costs[0], 100.0 * 1000.0 + 50.0 * 2000.0));
+        assert!(close(costs[1], 25.0 * 2000.0));
+        let p = &replay.positions[0];
+        assert!(close(p.quantity, 25.0));
+        assert!(close(p.cost, 25.0 * 2000.0));
+        assert!(close(p.realized, (450_000.0 - 200_000.0) + (62_500.0 - 50_000.0)));
+        assert_eq!(p.since, Some(day(2)));
+    }
+
+    #[test]
+    fn average_cost_spreads_cost_across_partial_sells() {
+        let replay = book(CostMethod::Average, partial_sells()).replay().unwrap();
+        let costs: Vec<f64> = replay.disposals.iter().map(|d| d.cost).collect();
+        assert!(close(costs[0], 150.0 * 1500.0));
+        assert!(close(costs[1], 25.0 * 1500.0));
+        let p = &replay.positions[0];
+        assert!(close(p.avg_cost(), 1500.0));
+        assert!(close(p.realized, (450_000.0 - 225_000.0) + (62_500.0 - 37_500.0)));
+    }
+
+    #[test]
+    fn fees_raise_cost_and_reduce_proceeds() {
+        let replay = book(CostMethod::Fifo, vec![
+            trade(1, Side::Buy, 100.0, 1000.0, 500.0),
+            trade(2, Side::Sell, 100.0, 1100.0, 400.0),
+        ]).replay().unwrap();
+        let d = &replay.disposals[0];
+        assert!(close(d.cost, 100_500.0));
+        assert!(close(d.proceeds, 109_600.0));
+        assert!(close(d.gain(), 9_100.0));
+        let p = &replay.positions[0];
+        assert!(!p.is_open());
+        assert!(close(p.fees, 900.0));
+        assert!(close(p.realized, 9_100.0));
+    }
+
+    #[test]
+    fn trades_replay_by_date_and_keep_entry_order_within_a_day() {
+        // Entered out of date order: the later buy is listed first.
+        let replay = book(CostMethod::Fifo, vec![
+            trade(5, Side::Buy, 10.0, 2000.0, 0.0),
+            trade(1, Side::Buy, 10.0, 1000.0, 0.0),
+            trade(5, Side::Sell, 15.0, 3000.0, 0.0),
+        ]).replay().unwrap();
+        assert!(close(replay.disposals[0].cost, 10.0 * 1000.0 + 5.0 * 2000.0));
+
+        let sell_first = book(CostMethod::Fifo, vec![
+            trade(1, Side::Sell, 10.0, 1000.0, 0.0),
+            trade(1, Side::Buy, 10.0, 1000.0, 0.0),
+        ]);
+        assert!(sell_first.replay().is_err());
+    }
+
+    #[test]
+    fn selling_more_than_held_is_rejected() {
+        let oversell = book(CostMethod::Average, vec![
+            trade(1, Side::Buy, 100.0, 1000.0, 0.0),
+            trade(2, Side::Sell, 100.5, 1000.0, 0.0),
+        ]);
+        assert!(oversell.replay().unwrap_err().contains("melebihi saldo"));
+    }
+
+    #[test]
+    fn ambiguous_thousands_dot_is_rejected() {
+        assert!(parse_trade("beli saham BBRI 1.000 4.500").is_err());
+        let t = parse_trade("beli saham BBRI 1000 4500 fee=6.750,50 2025-01-10").unwrap();
+        assert!(close(t.fee, 6750.5));
+        let t = parse_trade("beli kripto BTC 0,5 1.500.000.000").unwrap();
+        assert!(close(t.quantity, 0.5) && close(t.price, 1_500_000_000.0));
+        assert!(close(parse_trade("beli kripto BTC 0.001 1500000000").unwrap().quantity, 0.001));
+    }
+}
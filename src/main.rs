@@ -1,12 +1,32 @@
-﻿use teloxide::prelude::*;
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode, BotCommand};
+﻿use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode, BotCommand, InputFile, InputMedia, InputMediaPhoto};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use chrono::Utc;
+use chrono::{Datelike, Utc};
+use solana_sdk::native_token::sol_to_lamports;
 
 mod api;
+mod chart;
+mod compare;
+mod crypto_syariah;
+mod csvio;
+mod feed;
+mod holders;
+mod ledger;
+mod purification;
+mod rebalance;
+mod screener;
+mod signer;
+mod solana;
+mod staking;
+mod swap;
+mod syariah;
+mod tax;
+mod txdecode;
+mod zakat;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 struct UserKey { chat_id: ChatId, user_id: UserId }
@@ -14,25 +34,51 @@ impl UserKey { fn new(chat_id: ChatId, user_id: UserId) -> Self { Self { chat_id
 
 #[derive(Clone, PartialEq)]
 enum UserState { 
-    Idle, AwaitingCrypto, AwaitingStock, AwaitingSolanaTicker, AwaitingRealBuyCA,
+    Idle, AwaitingCrypto, AwaitingStock, AwaitingDexTicker(api::Chain), AwaitingRealBuyCA,
     AwaitingBuyTicker, AwaitingSellTicker, AwaitingAddWatchlist,
     AwaitingBroadcast, AwaitingBanUser, AwaitingUnbanUser, 
     AwaitingDirectMsg, AwaitingAddGroup, AwaitingRemoveGroup, AwaitingGiftPremium,
+    AwaitingPurification, AwaitingCoinRule, AwaitingRemoveCoinRule, AwaitingWalletAddress, AwaitingLedgerTrade,
+    AwaitingImport(Option<csvio::Preset>), AwaitingRebalanceTargets,
 }
 
 #[derive(Debug, Clone)]
-struct Holding { symbol: String, quantity: f64, avg_price: f64 }
+struct Holding { symbol: String, quantity: f64, avg_price: f64, since: i64 }
 #[derive(Debug, Clone)]
-struct UserPortfolio { balance: f64, holdings: HashMap<String, Holding> }
+struct UserPortfolio { balance: f64, holdings: HashMap<String, Holding>, since: i64 }
+#[derive(Debug, Clone, Default)]
+struct ZakatSettings { gold_price: Option<f64>, cash: Option<(f64, i64)> }
 
 struct AppState {
     states: Mutex<HashMap<UserKey, UserState>>,
     portfolios: Mutex<HashMap<ChatId, UserPortfolio>>,
     watchlist: Mutex<HashMap<ChatId, Vec<String>>>,
+    stock_watches: Mutex<HashMap<ChatId, HashSet<String>>>,
     users: Mutex<HashSet<ChatId>>,
     banned: Mutex<HashSet<ChatId>>,
     premium_groups: Mutex<HashSet<ChatId>>,
     premium_users: Mutex<HashSet<ChatId>>,
+    price_history: Mutex<HashMap<String, VecDeque<chart::Candle>>>,
+    issi_cache: Mutex<Option<(i64, Vec<api::SyariahApiResponse>)>>,
+    screener_filters: Mutex<HashMap<ChatId, screener::ScreenerFilter>>,
+    issi_snapshots: Mutex<HashMap<String, syariah::Snapshot>>,
+    purifications: Mutex<HashMap<ChatId, Vec<purification::PurificationRecord>>>,
+    pending_purifications: Mutex<HashMap<ChatId, Vec<purification::PurificationRecord>>>,
+    zakat_settings: Mutex<HashMap<ChatId, ZakatSettings>>,
+    coin_rules: Mutex<HashMap<String, crypto_syariah::CoinRule>>,
+    coin_filters: Mutex<HashMap<ChatId, crypto_syariah::CoinFilter>>,
+    pending_swaps: Mutex<HashMap<ChatId, swap::PendingSwap>>,
+    swap_spend: Mutex<swap::DailySpend>,
+    slippage_bps: Mutex<u16>,
+    signer: Option<Arc<dyn signer::TxSigner>>,
+    wallet_watches: Mutex<HashMap<ChatId, Vec<String>>>,
+    wallet_cursors: Mutex<HashMap<String, String>>,
+    feed_subscribers: Mutex<HashSet<ChatId>>,
+    feed_seen: Mutex<HashMap<String, i64>>,
+    ledgers: Mutex<HashMap<ChatId, ledger::Ledger>>,
+    pending_imports: Mutex<HashMap<ChatId, Vec<(usize, ledger::Trade)>>>,
+    rebalance_targets: Mutex<HashMap<ChatId, Vec<rebalance::Target>>>,
+    pending_rebalances: Mutex<HashMap<ChatId, Vec<rebalance::Order>>>,
 }
 
 fn format_angka(n: f64) -> String { 
@@ -46,6 +92,16 @@ fn format_angka(n: f64) -> String {
     res 
 }
 
+const ISSI_CACHE_TTL: i64 = 86_400;
+const CALLBACK_DATA_MAX: usize = 64;
+const MAX_WATCHED_WALLETS: usize = 5;
+const WALLET_POLL_PAGE: usize = 10;
+const WALLET_POLL_MAX_PAGES: usize = 10;
+const TAZKIYAH_PROMPT: &str = "🧼 <b>TAZKIYAH DIVIDEN</b>\n\nKirim satu saham per baris:\n<code>KODE LEMBAR DIVIDEN_PER_LEMBAR</code>\n\n<i>Contoh:\nBBRI 1000 150\nTLKM 500 120</i>\n\n<i>Saham yang tercatat di /ledger cukup ditulis <code>KODE DIVIDEN_PER_LEMBAR</code></i>\n\n<i>Seluruh portofolio: kirim <code>semua</code> (tambah <code>sim</code> untuk simulator, <code>tahun=2024</code> untuk tahun buku lain)</i>";
+const EXPORT_PROMPT: &str = "📤 <b>EKSPOR DATA</b>\n\nPilih data dan format. File dikirim sebagai dokumen.\n\n<i>Atau: /export trades csv, /export ledger json, /export sim csv, /export watchlist csv</i>";
+const REBALANCE_PROMPT: &str = "⚖️ <b>REBALANCE PORTOFOLIO</b>\n\nKirim target alokasi (total 100%), pisahkan dengan <code>/</code>:\n<code>60% saham / 30% BTC / 10% cash</code>\n\nTarget bisa kelas aset (<code>saham</code>, <code>kripto</code>, <code>token</code>, <code>kas</code>) atau simbol (<code>BTC</code>, <code>BBRI</code>).\n\n<i>Atau: /rebalance 50% kripto / 50% kas</i>";
+const LEDGER_PROMPT: &str = "📒 <b>CATAT TRANSAKSI RIIL</b>\n\nFormat (harga & fee dalam Rupiah):\n<code>beli|jual KELAS SIMBOL JUMLAH HARGA [fee=X] [YYYY-MM-DD]</code>\n<code>beli|jual kas JUMLAH [YYYY-MM-DD]</code>\n\nKelas: <code>saham</code>, <code>kripto</code>, <code>token</code> (CA Solana), <code>kas</code>\n\n<i>Contoh:\nbeli saham BBRI 1000 4500 fee=6750 2025-01-10\njual kripto BTC 0.01 1500000000 fee=15000\nbeli kas 25000000 2025-01-02</i>";
+
 fn is_admin(chat_id: i64) -> bool { chat_id == 6187914291 }
 
 fn get_welcome_text(name: &str) -> String {
@@ -67,6 +123,19 @@ fn get_welcome_text(name: &str) -> String {
 async fn main() {
     dotenv::dotenv().ok();
     pretty_env_logger::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("encrypt-keystore") {
+        let passphrase = std::env::var("SOLANA_KEYSTORE_PASSPHRASE").unwrap_or_default();
+        match (args.get(2), args.get(3)) {
+            (Some(input), Some(output)) if !passphrase.is_empty() => match signer::encrypt_keystore(input, output, &passphrase) {
+                Ok(pubkey) => println!("✅ Keystore {} dibuat untuk {}", output, pubkey),
+                Err(e) => eprintln!("❌ {}", e),
+            },
+            _ => eprintln!("Usage: SOLANA_KEYSTORE_PASSPHRASE=... lubix_bot encrypt-keystore <keypair.json> <keystore.json>"),
+        }
+        return;
+    }
     println!("🚀 LubixBot v9.5 Starting...");
     
     let bot = Bot::from_env();
@@ -75,20 +144,101 @@ async fn main() {
         BotCommand::new("kripto", "🪙 Cek Crypto"),
         BotCommand::new("saham", "🕌 Saham Syariah"),
         BotCommand::new("solana", "⚡️ Solana DEX"),
+        BotCommand::new("screener", "🔎 ISSI Screener"),
+        BotCommand::new("tazkiyah", "🧼 Purifikasi Dividen"),
+        BotCommand::new("zakat", "🕋 Kalkulator Zakat"),
+        BotCommand::new("ledger", "📒 Portofolio Riil"),
+        BotCommand::new("pajak", "💼 Laporan Pajak Tahunan"),
+        BotCommand::new("rebalance", "⚖️ Rebalance Portofolio"),
+        BotCommand::new("export", "📤 Ekspor CSV/JSON"),
+        BotCommand::new("import", "📥 Impor CSV Broker"),
+        BotCommand::new("compare", "⚖️ Bandingkan Ticker"),
+        BotCommand::new("wallet", "👛 Cek Wallet Solana"),
+        BotCommand::new("watchwallet", "👁 Pantau Wallet"),
+        BotCommand::new("tx", "🧾 Decode Transaksi"),
+        BotCommand::new("feed", "📡 Feed Pair Solana"),
+        BotCommand::new("staking", "🥩 Staking Solana"),
+        BotCommand::new("holders", "👥 Distribusi Holder"),
+        BotCommand::new("solstatus", "🌐 Status Jaringan Solana"),
         BotCommand::new("sim", "🎮 Trading Sim"),
         BotCommand::new("panel", "🔐 Admin"),
         BotCommand::new("help", "❓ Help"),
     ];
     let _ = bot.set_my_commands(commands).await;
 
+    let signer = match signer::load_signer() {
+        Ok(s) => { log::info!("Signer loaded: {} ({})", s.pubkey(), s.source()); Some(s) }
+        Err(e) => { log::warn!("Signer unavailable: {}", e); None }
+    };
+
     let app_state = Arc::new(AppState {
         states: Mutex::new(HashMap::new()),
         portfolios: Mutex::new(HashMap::new()),
         watchlist: Mutex::new(HashMap::new()),
+        stock_watches: Mutex::new(HashMap::new()),
         users: Mutex::new(HashSet::new()),
         banned: Mutex::new(HashSet::new()),
         premium_groups: Mutex::new(HashSet::new()),
         premium_users: Mutex::new(HashSet::new()),
+        price_history: Mutex::new(HashMap::new()),
+        issi_cache: Mutex::new(None),
+        screener_filters: Mutex::new(HashMap::new()),
+        issi_snapshots: Mutex::new(HashMap::new()),
+        purifications: Mutex::new(HashMap::new()),
+        pending_purifications: Mutex::new(HashMap::new()),
+        zakat_settings: Mutex::new(HashMap::new()),
+        coin_rules: Mutex::new(crypto_syariah::default_rules()),
+        coin_filters: Mutex::new(HashMap::new()),
+        pending_swaps: Mutex::new(HashMap::new()),
+        swap_spend: Mutex::new(swap::DailySpend::default()),
+        slippage_bps: Mutex::new(swap::default_slippage_bps()),
+        signer,
+        wallet_watches: Mutex::new(HashMap::new()),
+        wallet_cursors: Mutex::new(HashMap::new()),
+        feed_subscribers: Mutex::new(HashSet::new()),
+        feed_seen: Mutex::new(HashMap::new()),
+        ledgers: Mutex::new(HashMap::new()),
+        pending_imports: Mutex::new(HashMap::new()),
+        rebalance_targets: Mutex::new(HashMap::new()),
+        pending_rebalances: Mutex::new(HashMap::new()),
+    });
+
+    let issi_state = app_state.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = refresh_issi_cache(&issi_state).await { log::warn!("ISSI refresh failed: {}", e); }
+            tokio::time::sleep(std::time::Duration::from_secs(ISSI_CACHE_TTL as u64)).await;
+        }
+    });
+
+    let alert_bot = bot.clone();
+    let alert_state = app_state.clone();
+    tokio::spawn(async move {
+        let hours: u64 = std::env::var("ISSI_ALERT_INTERVAL_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(6);
+        loop {
+            run_issi_snapshot(&alert_bot, &alert_state).await;
+            tokio::time::sleep(std::time::Duration::from_secs(hours.max(1) * 3600)).await;
+        }
+    });
+
+    let watch_bot = bot.clone();
+    let watch_state = app_state.clone();
+    tokio::spawn(async move {
+        let secs: u64 = std::env::var("WALLET_WATCH_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+        loop {
+            poll_wallet_watches(&watch_bot, &watch_state).await;
+            tokio::time::sleep(std::time::Duration::from_secs(secs.max(10))).await;
+        }
+    });
+
+    let feed_bot = bot.clone();
+    let feed_state = app_state.clone();
+    tokio::spawn(async move {
+        let minutes: u64 = std::env::var("FEED_INTERVAL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(minutes.max(5) * 60)).await;
+            run_feed(&feed_bot, &feed_state).await;
+        }
     });
 
     let handler = dptree::entry()
@@ -112,6 +262,32 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
 
     state.users.lock().await.insert(chat_id);
 
+    if let Some(doc) = msg.document() {
+        // Only files sent for /import are touched; other documents (and the sender's flow) are left alone.
+        let caption_preset = msg.caption().and_then(|c| c.split_whitespace().find_map(csvio::Preset::parse));
+        let awaiting = match state.states.lock().await.get(&user_key) {
+            Some(UserState::AwaitingImport(p)) => Some(*p),
+            _ => None,
+        };
+        if awaiting.is_none() && caption_preset.is_none() {
+            return Ok(());
+        }
+        let is_csv = doc.file_name.as_deref().is_some_and(|n| n.to_lowercase().ends_with(".csv"))
+            || doc.mime_type.as_ref().is_some_and(|m| m.essence_str() == "text/csv");
+        if !is_csv {
+            if awaiting.is_some() {
+                bot.send_message(chat_id, "❌ Kirim file <code>.csv</code> untuk diimpor").parse_mode(ParseMode::Html).await?;
+            }
+            return Ok(());
+        }
+        if awaiting.is_some() {
+            state.states.lock().await.insert(user_key, UserState::Idle);
+        }
+        let (txt, has_trades) = import_document(&bot, &state, chat_id, doc, caption_preset.or(awaiting.flatten())).await;
+        bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_import_menu(has_trades)).await?;
+        return Ok(());
+    }
+
     match text {
         "/start" => {
             state.states.lock().await.insert(user_key, UserState::Idle);
@@ -129,9 +305,9 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                 .parse_mode(ParseMode::Html).await?;
         }
         "/solana" => {
-            state.states.lock().await.insert(user_key, UserState::AwaitingSolanaTicker);
-            bot.send_message(chat_id, "⚡️ <b>SOLANA DEX</b>\n\nMasukkan ticker atau CA:")
-                .parse_mode(ParseMode::Html).await?;
+            state.states.lock().await.insert(user_key, UserState::AwaitingDexTicker(api::Chain::Solana));
+            bot.send_message(chat_id, dex_prompt(api::Chain::Solana))
+                .parse_mode(ParseMode::Html).reply_markup(make_chain_menu(api::Chain::Solana)).await?;
         }
         "/sim" => {
             bot.send_message(chat_id, "🎮 <b>TRADING SIMULATOR</b>\n\n💰 Starting: $10,000\n\nGunakan tombol di bawah:")
@@ -191,6 +367,22 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                 🎯 <b>FITUR UTAMA</b>\n\
                 ├ 🪙 /kripto - Cek harga cryptocurrency\n\
                 ├ 🕌 /saham - Screening saham syariah\n\
+                ├ 🔎 /screener - Filter saham ISSI\n\
+                ├ 🧼 /tazkiyah - Purifikasi dividen\n\
+                ├ 🕋 /zakat - Kalkulator zakat maal\n\
+                ├ 📒 /ledger - Portofolio riil (saham, kripto, kas)\n\
+                ├ 💼 /pajak - Laporan pajak & capital gain\n\
+                ├ ⚖️ /rebalance - Target alokasi & rebalance\n\
+                ├ 📤 /export - Ekspor portofolio & watchlist\n\
+                ├ 📥 /import - Impor CSV broker/exchange\n\
+                ├ ⚖️ /compare - Bandingkan beberapa ticker\n\
+                ├ 👛 /wallet - Saldo wallet Solana\n\
+                ├ 👁 /watchwallet - Notifikasi aktivitas wallet\n\
+                ├ 🧾 /tx - Decode transaksi Solana\n\
+                ├ 📡 /feed - Feed pair trending & baru\n\
+                ├ 🥩 /staking - Stake account & reward Solana\n\
+                ├ 👥 /holders - Distribusi holder token SPL\n\
+                ├ 🌐 /solstatus - Status & fee jaringan Solana\n\
                 ├ ⚡️ /solana - Solana DEX tracker\n\
                 ├ 🎮 /sim - Trading simulator\n\
                 └ 🏠 /start - Kembali ke dashboard\n\n\
@@ -226,36 +418,291 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                 .disable_web_page_preview(true)
                 .reply_markup(make_help_menu()).await?;
         }
+        t if t.starts_with("/ledger") => {
+            let args = t.trim_start_matches("/ledger").trim();
+            let txt = match args.split_whitespace().next().map(str::to_lowercase).as_deref() {
+                None => ledger_report(&state, chat_id).await,
+                Some("riwayat") => ledger::format_trades(&state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default()),
+                Some("metode") => {
+                    let method = args.split_whitespace().nth(1).and_then(ledger::CostMethod::parse);
+                    match method {
+                        Some(m) => set_ledger_method(&state, chat_id, m).await,
+                        None => "❌ Metode: fifo atau avg".to_string(),
+                    }
+                }
+                Some("hapus") => {
+                    match args.split_whitespace().nth(1).and_then(|n| n.parse::<usize>().ok()) {
+                        Some(n) => remove_ledger_trade(&state, chat_id, n).await,
+                        None => "❌ Format: /ledger hapus NOMOR (lihat /ledger riwayat)".to_string(),
+                    }
+                }
+                Some(_) => record_ledger_trade(&state, chat_id, args).await,
+            };
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+        }
+        t if t.starts_with("/pajak") => {
+            let year = t.trim_start_matches("/pajak").trim().parse().unwrap_or_else(|_| Utc::now().year());
+            let txt = tax_report(&state, chat_id, year).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_tax_menu(year)).await?;
+        }
+        t if t.starts_with("/rebalance") => {
+            let args = t.trim_start_matches("/rebalance").trim();
+            match args {
+                "" => {
+                    let targets = state.rebalance_targets.lock().await.get(&chat_id).cloned();
+                    match targets {
+                        Some(targets) => {
+                            bot.send_message(chat_id, rebalance::format_targets(&targets)).parse_mode(ParseMode::Html).reply_markup(make_rebalance_menu(false)).await?;
+                        }
+                        None => {
+                            state.states.lock().await.insert(user_key, UserState::AwaitingRebalanceTargets);
+                            bot.send_message(chat_id, REBALANCE_PROMPT).parse_mode(ParseMode::Html).await?;
+                        }
+                    }
+                }
+                "sim" | "ledger" => {
+                    let venue = if args == "sim" { rebalance::Venue::Simulator } else { rebalance::Venue::Ledger };
+                    let (txt, executable) = rebalance_report(&state, chat_id, venue).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_rebalance_menu(executable)).await?;
+                }
+                _ => {
+                    let txt = set_rebalance_targets(&state, chat_id, args).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_rebalance_menu(false)).await?;
+                }
+            }
+        }
+        t if t.starts_with("/export") => {
+            let mut args = t.trim_start_matches("/export").split_whitespace();
+            match args.next() {
+                Some(what) => {
+                    let format = args.next().and_then(csvio::ExportFormat::parse).unwrap_or(csvio::ExportFormat::Csv);
+                    send_export(&bot, &state, chat_id, what, format).await?;
+                }
+                None => {
+                    bot.send_message(chat_id, EXPORT_PROMPT).parse_mode(ParseMode::Html).reply_markup(make_export_menu()).await?;
+                }
+            }
+        }
+        t if t.starts_with("/import") => {
+            let preset = t.trim_start_matches("/import").split_whitespace().next().and_then(csvio::Preset::parse);
+            state.states.lock().await.insert(user_key, UserState::AwaitingImport(preset));
+            bot.send_message(chat_id, csvio::format_presets()).parse_mode(ParseMode::Html).await?;
+        }
+        t if t.starts_with("/tazkiyah") => {
+            let args = t.trim_start_matches("/tazkiyah").trim();
+            if args.is_empty() {
+                state.states.lock().await.insert(user_key, UserState::AwaitingPurification);
+                bot.send_message(chat_id, TAZKIYAH_PROMPT).parse_mode(ParseMode::Html).reply_markup(make_purification_menu(false)).await?;
+            } else {
+                let (txt, has_records) = calculate_purification(&state, chat_id, args).await;
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_purification_menu(has_records)).await?;
+            }
+        }
+        t if t.starts_with("/zakat") => {
+            let args = t.trim_start_matches("/zakat").trim();
+            let (sim, args) = match args.split_once(char::is_whitespace).unwrap_or((args, "")) {
+                ("sim", rest) => (true, rest),
+                _ => (false, args),
+            };
+            match update_zakat_settings(&state, chat_id, args).await {
+                Ok(()) => {
+                    let report = build_zakat_report(&state, chat_id, sim).await;
+                    bot.send_message(chat_id, report).parse_mode(ParseMode::Html).reply_markup(make_zakat_menu(sim)).await?;
+                }
+                Err(e) => { bot.send_message(chat_id, format!("❌ {}\n\nContoh: /zakat emas=1450000 kas=25000000@2025-01-10 atau /zakat sim", e)).await?; }
+            }
+        }
+        t if t.starts_with("/compare") => {
+            match compare::parse_tickers(t.trim_start_matches("/compare")) {
+                Ok(tickers) => {
+                    let rows = compare::fetch_all(&tickers).await;
+                    let table = compare::format_table(&rows, &*state.coin_rules.lock().await);
+                    let keys = compare::chart_keys(&rows, 64 - "cmp:30D:".len());
+                    bot.send_message(chat_id, table).parse_mode(ParseMode::Html).reply_markup(make_compare_menu(keys.as_deref())).await?;
+                }
+                Err(e) => { bot.send_message(chat_id, format!("❌ {}\n\nContoh: /compare BBRI BBCA TLKM atau /compare BTC ETH SOL\nPaksa crypto/saham: /compare c:LINK s:BBRI", e)).await?; }
+            }
+        }
+        t if t.starts_with("/slippage") => {
+            if is_admin(chat_id.0) {
+                let arg = t.trim_start_matches("/slippage").trim();
+                let txt = if arg.is_empty() {
+                    format!("⚙️ Slippage real buy: <code>{} bps</code>\n\n<i>Ubah: /slippage 50 (1-{} bps)</i>", *state.slippage_bps.lock().await, swap::MAX_SLIPPAGE_BPS)
+                } else {
+                    match arg.parse::<u16>() {
+                        Ok(bps) if (1..=swap::MAX_SLIPPAGE_BPS).contains(&bps) => {
+                            *state.slippage_bps.lock().await = bps;
+                            format!("✅ Slippage diset ke <code>{} bps</code> ({:.2}%)", bps, bps as f64 / 100.0)
+                        }
+                        _ => format!("❌ Slippage harus 1-{} bps", swap::MAX_SLIPPAGE_BPS),
+                    }
+                };
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).await?;
+            }
+        }
+        t if t.starts_with("/tx") => {
+            let signature = t.trim_start_matches("/tx").trim();
+            if signature.is_empty() {
+                bot.send_message(chat_id, "🧾 <b>TX DECODER</b>\n\nFormat: <code>/tx SIGNATURE</code>").parse_mode(ParseMode::Html).await?;
+            } else {
+                let txt = match txdecode::fetch_transaction(&solana::rpc_client(), signature).await {
+                    Ok(tx) => {
+                        let mut mints: Vec<String> = tx.changes.iter().filter_map(|c| c.mint.clone()).collect();
+                        mints.sort();
+                        mints.dedup();
+                        mints.push(solana::WSOL_MINT.to_string());
+                        let prices = api::fetch_token_prices(&mints).await;
+                        txdecode::format_transaction(&tx, &prices)
+                    }
+                    Err(e) => format!("❌ {}", e),
+                };
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).disable_web_page_preview(true).reply_markup(make_back_menu()).await?;
+            }
+        }
+        t if t.starts_with("/holders") => {
+            let mint = t.trim_start_matches("/holders").trim();
+            if mint.is_empty() {
+                bot.send_message(chat_id, "👥 <b>HOLDER DISTRIBUTION</b>\n\nFormat: <code>/holders MINT</code>").parse_mode(ParseMode::Html).await?;
+            } else {
+                let txt = holders_report(mint).await;
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_holders_menu(mint)).await?;
+            }
+        }
+        t if t.starts_with("/staking") => {
+            let address = t.trim_start_matches("/staking").trim();
+            if address.is_empty() {
+                bot.send_message(chat_id, "🥩 <b>SOLANA STAKING</b>\n\nFormat: <code>/staking ADDRESS</code>").parse_mode(ParseMode::Html).await?;
+            } else {
+                let txt = staking_report(address).await;
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).disable_web_page_preview(true).reply_markup(make_back_menu()).await?;
+            }
+        }
+        "/solstatus" => {
+            let txt = solana_status_report().await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_solstatus_menu()).await?;
+        }
+        "/feed" => {
+            let txt = format_feed_status(&state, chat_id).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_feed_menu(&state, chat_id).await).await?;
+        }
+        t if t.starts_with("/watchwallet") => {
+            let address = t.trim_start_matches("/watchwallet").trim();
+            let txt = if address.is_empty() { format_wallet_watches(&state, chat_id).await } else { add_wallet_watch(&state, chat_id, address).await };
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_watch_menu(&state, chat_id).await).await?;
+        }
+        t if t.starts_with("/unwatchwallet") => {
+            let address = t.trim_start_matches("/unwatchwallet").trim();
+            let removed = remove_wallet_watch(&state, chat_id, address).await;
+            let txt = if removed { format!("✅ <code>{}</code> dihapus dari pantauan", address) } else { "❌ Wallet tidak ada di daftar pantauan".to_string() };
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_watch_menu(&state, chat_id).await).await?;
+        }
+        t if t.starts_with("/wallet") => {
+            let address = t.trim_start_matches("/wallet").trim();
+            if address.is_empty() {
+                state.states.lock().await.insert(user_key, UserState::AwaitingWalletAddress);
+                bot.send_message(chat_id, "👛 <b>SOLANA WALLET</b>\n\nMasukkan address wallet:").parse_mode(ParseMode::Html).await?;
+            } else {
+                let txt = wallet_report(address).await;
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_menu(address)).await?;
+            }
+        }
+        t if t.starts_with("/screener") => {
+            match screener::parse_filter(t.trim_start_matches("/screener")) {
+                Ok(filter) => {
+                    state.screener_filters.lock().await.insert(chat_id, filter);
+                    match render_screener(&state, chat_id, 0).await {
+                        Ok((txt, kb)) => { bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(kb).await?; }
+                        Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+                    }
+                }
+                Err(e) => { bot.send_message(chat_id, format!("❌ {}\n\nContoh: /screener sektor=Energi cap=10-500 hutang=30 sort=cap", e)).await?; }
+            }
+        }
         _ => {
             let current_state = state.states.lock().await.get(&user_key).cloned().unwrap_or(UserState::Idle);
             match current_state {
                 UserState::AwaitingCrypto => {
-                    let result = api::fetch_crypto_from_cmc(text).await;
+                    let rule = get_coin_rule(&state, text.trim()).await;
+                    let result = api::fetch_crypto_from_cmc(text, &rule).await;
                     match result {
-                        Ok(data) => { bot.send_message(chat_id, data).parse_mode(ParseMode::Html).reply_markup(make_back_menu()).await?; }
+                        Ok(data) => { bot.send_message(chat_id, data).parse_mode(ParseMode::Html).reply_markup(make_result_menu("c", &text.trim().to_uppercase())).await?; }
                         Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
                     }
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
                 UserState::AwaitingStock => {
-                    let result = api::fetch_stock_from_api(text).await;
+                    let result = stock_card(&state, text.trim(), syariah::Standard::OjkDsnMui).await;
                     match result {
-                        Ok(data) => { bot.send_message(chat_id, data).parse_mode(ParseMode::Html).reply_markup(make_back_menu()).await?; }
+                        Ok(data) => { bot.send_message(chat_id, data).parse_mode(ParseMode::Html).reply_markup(make_stock_menu(text.trim(), syariah::Standard::OjkDsnMui)).await?; }
                         Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
                     }
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
-                UserState::AwaitingSolanaTicker => {
-                    let result = api::fetch_solana_token(text).await;
-                    match result {
-                        Ok(data) => { bot.send_message(chat_id, data).parse_mode(ParseMode::Html).reply_markup(make_back_menu()).await?; }
+                UserState::AwaitingDexTicker(chain) => {
+                    // "PEPE eth" overrides the chain picked from the keyboard.
+                    let mut parts = text.split_whitespace();
+                    let query = parts.next().unwrap_or("").to_string();
+                    let chain = parts.next().and_then(api::Chain::parse).unwrap_or(chain);
+                    match api::search_dex_tokens(&query, chain).await {
+                        Ok(tokens) => match api::dominant_token(&tokens) {
+                            Some(pair) => {
+                                record_dex_price(&state, pair).await;
+                                let card = api::format_dex_token(pair).await;
+                                bot.send_message(chat_id, card).parse_mode(ParseMode::Html).reply_markup(make_dex_token_menu(pair)).await?;
+                            }
+                            None => {
+                                bot.send_message(chat_id, api::format_token_choices(&query, chain, &tokens))
+                                    .parse_mode(ParseMode::Html).reply_markup(make_token_choice_menu(chain, &tokens)).await?;
+                            }
+                        },
                         Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
                     }
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
                 UserState::AwaitingRealBuyCA => {
-                    bot.send_message(chat_id, format!("🚀 <b>REAL BUY</b>\n\nCA: <code>{}</code>\n\n⚠️ Feature dalam pengembangan!", text))
-                        .parse_mode(ParseMode::Html).await?;
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                    if !is_admin(chat_id.0) { return Ok(()); }
+                    let mut parts = text.split_whitespace();
+                    let mint = parts.next().unwrap_or("").to_string();
+                    // Rounded to 0.0001 SOL so the amount fits the re-quote callback and matches what is quoted.
+                    let amount = parts.next().and_then(|a| a.replace(',', ".").parse::<f64>().ok()).map(|a| (a * 10_000.0).round() / 10_000.0);
+                    if let Err(e) = solana::parse_pubkey(&mint) {
+                        bot.send_message(chat_id, format!("❌ {}", e)).await?;
+                        return Ok(());
+                    }
+                    let safety = match api::fetch_solana_token(&mint).await {
+                        Ok(card) => card,
+                        Err(e) => format!("❌ {}", e),
+                    };
+                    bot.send_message(chat_id, safety).parse_mode(ParseMode::Html).await?;
+                    match amount {
+                        Some(sol) => match quote_real_buy(&state, chat_id, &mint, sol).await {
+                            Ok(txt) => { bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_real_buy_confirm_menu(&mint, sol)).await?; }
+                            Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+                        },
+                        None => {
+                            bot.send_message(chat_id, "🚀 <b>REAL BUY</b>\n\nPilih jumlah SOL:").parse_mode(ParseMode::Html).reply_markup(make_real_buy_amount_menu(&mint)).await?;
+                        }
+                    }
+                }
+                UserState::AwaitingWalletAddress => {
+                    let txt = wallet_report(text).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_menu(text.trim())).await?;
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                }
+                UserState::AwaitingRebalanceTargets => {
+                    let txt = set_rebalance_targets(&state, chat_id, text).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_rebalance_menu(false)).await?;
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                }
+                UserState::AwaitingLedgerTrade => {
+                    let txt = record_ledger_trade(&state, chat_id, text).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                }
+                UserState::AwaitingPurification => {
+                    let (txt, has_records) = calculate_purification(&state, chat_id, text).await;
+                    bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_purification_menu(has_records)).await?;
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
                 UserState::AwaitingBuyTicker => {
@@ -276,8 +723,13 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                 }
                 UserState::AwaitingAddWatchlist => {
                     let ticker = text.trim().to_uppercase();
-                    state.watchlist.lock().await.entry(chat_id).or_insert_with(Vec::new).push(ticker.clone());
-                    bot.send_message(chat_id, format!("✅ {} ditambahkan ke watchlist!", ticker)).reply_markup(make_watchlist_menu()).await?;
+                    match check_coin_filter(&state, chat_id, &ticker).await {
+                        Ok(()) => {
+                            state.watchlist.lock().await.entry(chat_id).or_insert_with(Vec::new).push(ticker.clone());
+                            bot.send_message(chat_id, format!("✅ {} ditambahkan ke watchlist!", ticker)).reply_markup(make_watchlist_menu()).await?;
+                        }
+                        Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).reply_markup(make_watchlist_menu()).await?; }
+                    }
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
                 UserState::AwaitingBroadcast => {
@@ -332,10 +784,34 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                     }
                     state.states.lock().await.insert(user_key, UserState::Idle);
                 }
+                UserState::AwaitingCoinRule => {
+                    if is_admin(chat_id.0) {
+                        match crypto_syariah::parse_rule(text) {
+                            Ok((symbol, rule)) => {
+                                let label = rule.status.label();
+                                state.coin_rules.lock().await.insert(symbol.clone(), rule);
+                                bot.send_message(chat_id, format!("✅ {} → {}", symbol, label)).reply_markup(make_coin_rules_menu()).await?;
+                            }
+                            Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).reply_markup(make_coin_rules_menu()).await?; }
+                        }
+                    }
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                }
+                UserState::AwaitingRemoveCoinRule => {
+                    if is_admin(chat_id.0) {
+                        let symbol = text.trim().to_uppercase();
+                        let removed = state.coin_rules.lock().await.remove(&symbol).is_some();
+                        let reply = if removed { format!("✅ Rule {} dihapus!", symbol) } else { format!("❌ Rule {} tidak ada", symbol) };
+                        bot.send_message(chat_id, reply).reply_markup(make_coin_rules_menu()).await?;
+                    }
+                    state.states.lock().await.insert(user_key, UserState::Idle);
+                }
                 UserState::AwaitingAddGroup => {
                     if is_admin(chat_id.0) {
                         if let Ok(gid) = text.trim().parse::<i64>() {
                             state.premium_groups.lock().await.insert(ChatId(gid));
+                            // Premium groups get the feed by default but can still turn it off with feed_toggle.
+                            state.feed_subscribers.lock().await.insert(ChatId(gid));
                             bot.send_message(chat_id, format!("✅ Group {} added!", gid)).reply_markup(make_admin_action_menu()).await?;
                         }
                     }
@@ -345,6 +821,7 @@ async fn message_handler(bot: Bot, msg: Message, state: Arc<AppState>) -> Respon
                     if is_admin(chat_id.0) {
                         if let Ok(gid) = text.trim().parse::<i64>() {
                             state.premium_groups.lock().await.remove(&ChatId(gid));
+                            state.feed_subscribers.lock().await.remove(&ChatId(gid));
                             bot.send_message(chat_id, format!("✅ Group {} removed!", gid)).reply_markup(make_admin_action_menu()).await?;
                         }
                     }
@@ -397,8 +874,43 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
             bot.send_message(chat_id, "🕌 <b>SAHAM SYARIAH</b>\n\nMasukkan kode:\n<i>Contoh: BBRI, TLKM, ADRO</i>").parse_mode(ParseMode::Html).await?;
         }
         "menu_solana" => {
-            state.states.lock().await.insert(user_key, UserState::AwaitingSolanaTicker);
-            bot.send_message(chat_id, "⚡️ <b>SOLANA DEX</b>\n\nMasukkan ticker/CA:").parse_mode(ParseMode::Html).await?;
+            state.states.lock().await.insert(user_key, UserState::AwaitingDexTicker(api::Chain::Solana));
+            bot.send_message(chat_id, dex_prompt(api::Chain::Solana)).parse_mode(ParseMode::Html).reply_markup(make_chain_menu(api::Chain::Solana)).await?;
+        }
+        d if d.starts_with("stake:") => {
+            let txt = staking_report(&d[6..]).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).disable_web_page_preview(true).reply_markup(make_back_menu()).await?;
+        }
+        d if d.starts_with("holders:") => {
+            let mint = &d[8..];
+            let txt = holders_report(mint).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_holders_menu(mint)).await?;
+        }
+        d if d.starts_with("holders_pie:") => {
+            send_holders_pie(&bot, chat_id, &d[12..]).await?;
+        }
+        "solstatus_refresh" => {
+            if let Some(message) = q.message.as_ref() {
+                let txt = solana_status_report().await;
+                bot.edit_message_text(chat_id, message.id, txt).parse_mode(ParseMode::Html).reply_markup(make_solstatus_menu()).await?;
+            }
+        }
+        d if d.starts_with("dex_chain:") => {
+            let chain = api::Chain::parse(&d[10..]).unwrap_or(api::Chain::Solana);
+            state.states.lock().await.insert(user_key, UserState::AwaitingDexTicker(chain));
+            bot.send_message(chat_id, dex_prompt(chain)).parse_mode(ParseMode::Html).reply_markup(make_chain_menu(chain)).await?;
+        }
+        d if d.starts_with("dex:") => {
+            let (code, address) = d[4..].split_once(':').unwrap_or(("sol", ""));
+            let chain = api::Chain::parse(code).unwrap_or(api::Chain::Solana);
+            match api::search_dex_tokens(address, chain).await {
+                Ok(tokens) => {
+                    record_dex_price(&state, &tokens[0]).await;
+                    let card = api::format_dex_token(&tokens[0]).await;
+                    bot.send_message(chat_id, card).parse_mode(ParseMode::Html).reply_markup(make_dex_token_menu(&tokens[0])).await?;
+                }
+                Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+            }
         }
         "menu_sim_main" => {
             bot.send_message(chat_id, "🎮 <b>TRADING SIMULATOR</b>\n\n💰 Starting: $10,000").parse_mode(ParseMode::Html).reply_markup(make_sim_menu()).await?;
@@ -418,11 +930,16 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
         "menu_watchlist" => {
             let wl = state.watchlist.lock().await;
             let items = wl.get(&chat_id).cloned().unwrap_or_default();
-            let txt = if items.is_empty() { 
+            let mut txt = if items.is_empty() { 
                 "⭐ <b>WATCHLIST</b>\n\n📭 Kosong".to_string() 
             } else {
                 format!("⭐ <b>WATCHLIST</b>\n\n{}", items.iter().enumerate().map(|(i,s)| format!("{}. <code>{}</code>", i+1, s)).collect::<Vec<_>>().join("\n"))
             };
+            let mut stocks: Vec<String> = state.stock_watches.lock().await.get(&chat_id).map(|s| s.iter().cloned().collect()).unwrap_or_default();
+            if !stocks.is_empty() {
+                stocks.sort();
+                txt.push_str(&format!("\n\n🔔 <b>Saham (notifikasi ISSI):</b>\n<code>{}</code>", stocks.join(", ")));
+            }
             bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_watchlist_menu()).await?;
         }
         "watchlist_add" => {
@@ -431,6 +948,7 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
         }
         "watchlist_clear" => {
             state.watchlist.lock().await.remove(&chat_id);
+            state.stock_watches.lock().await.remove(&chat_id);
             bot.send_message(chat_id, "✅ Watchlist cleared!").reply_markup(make_main_menu()).await?;
         }
         "watchlist_check" => {
@@ -439,18 +957,74 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
                 bot.send_message(chat_id, "📭 Watchlist kosong!").await?; 
             } else {
                 let mut res = "📊 <b>WATCHLIST PRICES</b>\n\n".to_string();
+                let filter = state.coin_filters.lock().await.get(&chat_id).copied().unwrap_or_default();
+                let mut hidden = Vec::new();
                 for t in &wl {
-                    match api::fetch_crypto_from_cmc(t).await {
+                    let rule = get_coin_rule(&state, t).await;
+                    if !filter.allows(&rule) {
+                        hidden.push(t.clone());
+                        continue;
+                    }
+                    match api::fetch_crypto_from_cmc(t, &rule).await {
                         Ok(d) => res.push_str(&format!("{}\n\n", d)),
                         Err(_) => res.push_str(&format!("❌ {} - Error\n", t)),
                     }
                 }
+                if !hidden.is_empty() {
+                    res.push_str(&format!("🕌 Disembunyikan oleh filter ({}): <code>{}</code>", filter.label(), hidden.join(", ")));
+                }
                 bot.send_message(chat_id, res).parse_mode(ParseMode::Html).reply_markup(make_watchlist_menu()).await?;
             }
         }
         "menu_solana_real" => {
-            state.states.lock().await.insert(user_key, UserState::AwaitingRealBuyCA);
-            bot.send_message(chat_id, "🚀 <b>REAL BUY SOLANA</b>\n\n⚠️ Transaksi NYATA!\n\nMasukkan CA token:").parse_mode(ParseMode::Html).await?;
+            if is_admin(chat_id.0) {
+                state.states.lock().await.insert(user_key, UserState::AwaitingRealBuyCA);
+                let limits = swap::SwapLimits::from_env();
+                let slippage = *state.slippage_bps.lock().await;
+                bot.send_message(chat_id, format!(
+                    "🚀 <b>REAL BUY SOLANA</b>\n\n{}\n\n• Maks/transaksi: <code>{} SOL</code>\n• Maks/hari: <code>{} SOL</code>\n• Slippage: <code>{:.2}%</code>\n\nMasukkan CA token (opsional jumlah SOL):\n<code>CA 0.1</code>",
+                    if swap::dry_run_forced() { "🧪 <b>DRY-RUN MODE</b> aktif, tidak ada transaksi yang dikirim" } else { "⚠️ Transaksi NYATA!" },
+                    limits.max_trade_sol, limits.daily_cap_sol, slippage as f64 / 100.0
+                )).parse_mode(ParseMode::Html).await?;
+            } else {
+                bot.send_message(chat_id, "⛔ REAL BUY hanya untuk admin").await?;
+            }
+        }
+        d if d.starts_with("rbq:") => {
+            if is_admin(chat_id.0) {
+                let mut parts = d.trim_start_matches("rbq:").splitn(2, ':');
+                let sol = parts.next().and_then(|a| a.parse::<f64>().ok()).unwrap_or(0.0);
+                let mint = parts.next().unwrap_or("");
+                match quote_real_buy(&state, chat_id, mint, sol).await {
+                    Ok(txt) => { bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_real_buy_confirm_menu(mint, sol)).await?; }
+                    Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+                }
+            }
+        }
+        "rb_confirm" => {
+            if is_admin(chat_id.0) {
+                let note = if swap::dry_run_forced() { "⏳ DRY-RUN MODE: simulasi saja..." } else { "⏳ Simulasi & mengirim transaksi..." };
+                bot.send_message(chat_id, note).await?;
+                let txt = match confirm_real_buy(&state, chat_id).await {
+                    Ok(receipt) => receipt,
+                    Err(e) => format!("❌ {}", api::escape_html(&e)),
+                };
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_back_menu()).await?;
+            }
+        }
+        "rb_dry" => {
+            if is_admin(chat_id.0) {
+                bot.send_message(chat_id, "⏳ Simulasi transaksi...").await?;
+                let txt = match dry_run_real_buy(&state, chat_id).await {
+                    Ok(report) => report,
+                    Err(e) => format!("❌ {}", api::escape_html(&e)),
+                };
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).await?;
+            }
+        }
+        "rb_cancel" => {
+            state.pending_swaps.lock().await.remove(&chat_id);
+            bot.send_message(chat_id, "❌ Real buy dibatalkan").reply_markup(make_back_menu()).await?;
         }
         "menu_help" => {
             let help_text = format!(
@@ -459,6 +1033,22 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
                 🎯 <b>FITUR UTAMA</b>\n\
                 ├ 🪙 /kripto - Cek harga cryptocurrency\n\
                 ├ 🕌 /saham - Screening saham syariah\n\
+                ├ 🔎 /screener - Filter saham ISSI\n\
+                ├ 🧼 /tazkiyah - Purifikasi dividen\n\
+                ├ 🕋 /zakat - Kalkulator zakat maal\n\
+                ├ 📒 /ledger - Portofolio riil (saham, kripto, kas)\n\
+                ├ 💼 /pajak - Laporan pajak & capital gain\n\
+                ├ ⚖️ /rebalance - Target alokasi & rebalance\n\
+                ├ 📤 /export - Ekspor portofolio & watchlist\n\
+                ├ 📥 /import - Impor CSV broker/exchange\n\
+                ├ ⚖️ /compare - Bandingkan beberapa ticker\n\
+                ├ 👛 /wallet - Saldo wallet Solana\n\
+                ├ 👁 /watchwallet - Notifikasi aktivitas wallet\n\
+                ├ 🧾 /tx - Decode transaksi Solana\n\
+                ├ 📡 /feed - Feed pair trending & baru\n\
+                ├ 🥩 /staking - Stake account & reward Solana\n\
+                ├ 👥 /holders - Distribusi holder token SPL\n\
+                ├ 🌐 /solstatus - Status & fee jaringan Solana\n\
                 ├ ⚡️ /solana - Solana DEX tracker\n\
                 ├ 🎮 /sim - Trading simulator\n\
                 └ 🏠 /start - Kembali ke dashboard\n\n\
@@ -507,7 +1097,7 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
             let mut total = portfolio.balance;
             let mut holdings_txt = String::new();
             for (sym, h) in &portfolio.holdings {
-                let price = get_price(sym).await;
+                let price = get_price(&state, sym).await;
                 let val = h.quantity * price;
                 total += val;
                 let pnl = val - (h.quantity * h.avg_price);
@@ -613,11 +1203,229 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
                 bot.send_message(chat_id, "➖ Masukkan Group ID:").await?;
             }
         }
-        "admin_wallet" | "admin_analytics" | "admin_system" => {
+        "admin_wallet" => {
+            if is_admin(chat_id.0) {
+                let txt = match &state.signer {
+                    Some(s) => admin_wallet_report(s.as_ref()).await,
+                    None => "💰 <b>BOT WALLET</b>\n━━━━━━━━━━━━━━━━━━━━━━━\n⚠️ Signer belum dikonfigurasi\n\n<i>Set SOLANA_KEYSTORE + SOLANA_KEYSTORE_PASSPHRASE atau SOLANA_KEYPAIR_PATH</i>".to_string(),
+                };
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).disable_web_page_preview(true).reply_markup(make_admin_action_menu()).await?;
+            }
+        }
+        "admin_analytics" | "admin_system" => {
             if is_admin(chat_id.0) {
                 bot.send_message(chat_id, "🔧 <b>SYSTEM STATUS</b>\n\n🟢 All systems operational").parse_mode(ParseMode::Html).reply_markup(make_admin_action_menu()).await?;
             }
         }
+        "tzk_save" => {
+            let pending = state.pending_purifications.lock().await.remove(&chat_id).unwrap_or_default();
+            let count = pending.len();
+            state.purifications.lock().await.entry(chat_id).or_insert_with(Vec::new).extend(pending);
+            bot.send_message(chat_id, format!("✅ {} catatan tazkiyah disimpan!", count)).reply_markup(make_purification_menu(false)).await?;
+        }
+        "tzk_summary" => {
+            let records = state.purifications.lock().await.get(&chat_id).cloned().unwrap_or_default();
+            bot.send_message(chat_id, purification::format_summary(&records)).parse_mode(ParseMode::Html).reply_markup(make_purification_menu(false)).await?;
+        }
+        "menu_coin_filter" => {
+            let current = state.coin_filters.lock().await.get(&chat_id).copied().unwrap_or_default();
+            bot.send_message(chat_id, format!("🕌 <b>FILTER SYARIAH KRIPTO</b>\n\nAktif: <code>{}</code>\n\nFilter berlaku untuk watchlist dan simulator.", current.label()))
+                .parse_mode(ParseMode::Html).reply_markup(make_coin_filter_menu(current)).await?;
+        }
+        d if d.starts_with("cf:") => {
+            if let (Some(filter), Some(message)) = (crypto_syariah::CoinFilter::parse(d.trim_start_matches("cf:")), q.message.as_ref()) {
+                state.coin_filters.lock().await.insert(chat_id, filter);
+                bot.edit_message_text(chat_id, message.id, format!("🕌 <b>FILTER SYARIAH KRIPTO</b>\n\nAktif: <code>{}</code>\n\nFilter berlaku untuk watchlist dan simulator.", filter.label()))
+                    .parse_mode(ParseMode::Html).reply_markup(make_coin_filter_menu(filter)).await?;
+            }
+        }
+        "admin_coin_rules" => {
+            if is_admin(chat_id.0) {
+                let list = crypto_syariah::format_rule_list(&*state.coin_rules.lock().await);
+                bot.send_message(chat_id, list).parse_mode(ParseMode::Html).reply_markup(make_coin_rules_menu()).await?;
+            }
+        }
+        "admin_coin_set" => {
+            if is_admin(chat_id.0) {
+                state.states.lock().await.insert(user_key, UserState::AwaitingCoinRule);
+                bot.send_message(chat_id, "🕌 Format: SYMBOL|status|reason|catatan\n\nContoh: AAVE|haram|lending|Pinjam-meminjam berbunga").await?;
+            }
+        }
+        "admin_coin_del" => {
+            if is_admin(chat_id.0) {
+                state.states.lock().await.insert(user_key, UserState::AwaitingRemoveCoinRule);
+                bot.send_message(chat_id, "➖ Masukkan SYMBOL:").await?;
+            }
+        }
+        "menu_wallet" => {
+            state.states.lock().await.insert(user_key, UserState::AwaitingWalletAddress);
+            bot.send_message(chat_id, "👛 <b>SOLANA WALLET</b>\n\nMasukkan address wallet:").parse_mode(ParseMode::Html).await?;
+        }
+        "menu_wallet_watch" => {
+            let txt = format_wallet_watches(&state, chat_id).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_watch_menu(&state, chat_id).await).await?;
+        }
+        d if d.starts_with("ww_add:") => {
+            let txt = add_wallet_watch(&state, chat_id, d.trim_start_matches("ww_add:")).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_watch_menu(&state, chat_id).await).await?;
+        }
+        d if d.starts_with("ww_del:") => {
+            remove_wallet_watch(&state, chat_id, d.trim_start_matches("ww_del:")).await;
+            let txt = format_wallet_watches(&state, chat_id).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_wallet_watch_menu(&state, chat_id).await).await?;
+        }
+        "menu_feed" => {
+            let txt = format_feed_status(&state, chat_id).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_feed_menu(&state, chat_id).await).await?;
+        }
+        "feed_toggle" => {
+            {
+                let mut subs = state.feed_subscribers.lock().await;
+                if !subs.remove(&chat_id) { subs.insert(chat_id); }
+            }
+            if let Some(message) = q.message.as_ref() {
+                let txt = format_feed_status(&state, chat_id).await;
+                bot.edit_message_text(chat_id, message.id, txt).parse_mode(ParseMode::Html).reply_markup(make_feed_menu(&state, chat_id).await).await?;
+            }
+        }
+        "feed_preview" => {
+            bot.send_message(chat_id, "⏳ Mengambil pair terbaru...").await?;
+            let txt = match feed::build_digest(&feed::FeedFilter::from_env(), &HashMap::new()).await {
+                Ok(items) => feed::format_digest(&items),
+                Err(e) => format!("❌ {}", e),
+            };
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).disable_web_page_preview(true).reply_markup(make_back_menu()).await?;
+        }
+        "menu_export" => {
+            bot.send_message(chat_id, EXPORT_PROMPT).parse_mode(ParseMode::Html).reply_markup(make_export_menu()).await?;
+        }
+        d if d.starts_with("exp:") => {
+            let (what, format) = d[4..].split_once(':').unwrap_or((&d[4..], "csv"));
+            send_export(&bot, &state, chat_id, what, csvio::ExportFormat::parse(format).unwrap_or(csvio::ExportFormat::Csv)).await?;
+        }
+        "ledger_import" => {
+            state.states.lock().await.insert(user_key, UserState::AwaitingImport(None));
+            bot.send_message(chat_id, csvio::format_presets()).parse_mode(ParseMode::Html).await?;
+        }
+        "imp_confirm" => {
+            let pending = state.pending_imports.lock().await.remove(&chat_id).unwrap_or_default();
+            let (added, errors) = {
+                let mut ledgers = state.ledgers.lock().await;
+                csvio::apply_import(ledgers.entry(chat_id).or_default(), &pending)
+            };
+            let mut txt = format!("✅ {} transaksi diimpor ke ledger", added);
+            for e in errors.iter().take(10) {
+                txt.push_str(&format!("\n❌ {}", api::escape_html(e)));
+            }
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+        }
+        "imp_cancel" => {
+            state.pending_imports.lock().await.remove(&chat_id);
+            bot.send_message(chat_id, "❌ Impor dibatalkan").reply_markup(make_back_menu()).await?;
+        }
+        "menu_ledger" => {
+            let txt = ledger_report(&state, chat_id).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+        }
+        "ledger_add" => {
+            state.states.lock().await.insert(user_key, UserState::AwaitingLedgerTrade);
+            bot.send_message(chat_id, LEDGER_PROMPT).parse_mode(ParseMode::Html).await?;
+        }
+        "ledger_trades" => {
+            let txt = ledger::format_trades(&state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default());
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+        }
+        "rebal_set" => {
+            state.states.lock().await.insert(user_key, UserState::AwaitingRebalanceTargets);
+            bot.send_message(chat_id, REBALANCE_PROMPT).parse_mode(ParseMode::Html).await?;
+        }
+        d if d.starts_with("rebal:") => {
+            if !state.rebalance_targets.lock().await.contains_key(&chat_id) {
+                state.states.lock().await.insert(user_key, UserState::AwaitingRebalanceTargets);
+                bot.send_message(chat_id, REBALANCE_PROMPT).parse_mode(ParseMode::Html).await?;
+            } else {
+                let venue = if &d[6..] == "sim" { rebalance::Venue::Simulator } else { rebalance::Venue::Ledger };
+                let (txt, executable) = rebalance_report(&state, chat_id, venue).await;
+                bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_rebalance_menu(executable)).await?;
+            }
+        }
+        "rebal_exec" => {
+            let orders = state.pending_rebalances.lock().await.remove(&chat_id).unwrap_or_default();
+            let txt = if orders.is_empty() {
+                "❌ Tidak ada order rebalance yang menunggu. Hitung ulang dulu.".to_string()
+            } else {
+                execute_rebalance(&state, chat_id, orders).await
+            };
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_sim_menu()).await?;
+        }
+        d if d.starts_with("tax:") => {
+            let year = d[4..].parse().unwrap_or_else(|_| Utc::now().year());
+            let txt = tax_report(&state, chat_id, year).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_tax_menu(year)).await?;
+        }
+        d if d.starts_with("tax_exp:") => {
+            let mut parts = d[8..].split(':');
+            let year = parts.next().and_then(|y| y.parse().ok()).unwrap_or_else(|| Utc::now().year());
+            send_tax_export(&bot, &state, chat_id, year, parts.next().unwrap_or("csv")).await?;
+        }
+        d if d.starts_with("ledger_method:") => {
+            let method = ledger::CostMethod::parse(&d[14..]).unwrap_or_default();
+            let txt = set_ledger_method(&state, chat_id, method).await;
+            bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(make_ledger_menu(&state, chat_id).await).await?;
+        }
+        "menu_zakat" | "zakat_sim" => {
+            let sim = data == "zakat_sim";
+            let report = build_zakat_report(&state, chat_id, sim).await;
+            bot.send_message(chat_id, report).parse_mode(ParseMode::Html).reply_markup(make_zakat_menu(sim)).await?;
+        }
+        "menu_screener" => {
+            state.screener_filters.lock().await.entry(chat_id).or_default();
+            match render_screener(&state, chat_id, 0).await {
+                Ok((txt, kb)) => { bot.send_message(chat_id, txt).parse_mode(ParseMode::Html).reply_markup(kb).await?; }
+                Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+            }
+        }
+        d if d.starts_with("scr_page:") || d.starts_with("scr_sort:") => {
+            let page = if let Some(field) = d.strip_prefix("scr_sort:").and_then(screener::SortField::parse) {
+                let mut filters = state.screener_filters.lock().await;
+                let filter = filters.entry(chat_id).or_default();
+                if filter.sort == field { filter.desc = !filter.desc; } else { filter.sort = field; filter.desc = true; }
+                0
+            } else {
+                d.trim_start_matches("scr_page:").parse().unwrap_or(0)
+            };
+            if let Some(message) = q.message.as_ref() {
+                match render_screener(&state, chat_id, page).await {
+                    Ok((txt, kb)) => { bot.edit_message_text(chat_id, message.id, txt).parse_mode(ParseMode::Html).reply_markup(kb).await?; }
+                    Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+                }
+            }
+        }
+        d if d.starts_with("wl_add:") => {
+            let ticker = d.trim_start_matches("wl_add:").to_uppercase();
+            state.stock_watches.lock().await.entry(chat_id).or_default().insert(ticker.clone());
+            bot.send_message(chat_id, format!("✅ {} ditambahkan ke watchlist!\n🔔 Kamu akan mendapat notifikasi perubahan status ISSI.", ticker)).reply_markup(make_watchlist_menu()).await?;
+        }
+        d if d.starts_with("syariah:") => {
+            let parts: Vec<&str> = d.splitn(3, ':').collect();
+            if let (Some(message), [_, code, ticker]) = (q.message.as_ref(), parts.as_slice()) {
+                let standard = syariah::Standard::parse(code).unwrap_or(syariah::Standard::OjkDsnMui);
+                match stock_card(&state, ticker, standard).await {
+                    Ok(data) => { bot.edit_message_text(chat_id, message.id, data).parse_mode(ParseMode::Html).reply_markup(make_stock_menu(ticker, standard)).await?; }
+                    Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).await?; }
+                }
+            }
+        }
+        d if d.starts_with("cmp:") => {
+            if let Some(message) = q.message.as_ref() {
+                handle_compare_chart(&bot, message, d).await?;
+            }
+        }
+        d if d.starts_with("chart:") => {
+            if let Some(message) = q.message.as_ref() {
+                handle_chart(&bot, &state, message, d).await?;
+            }
+        }
         _ => { bot.send_message(chat_id, "✅ Feature aktif!").await?; }
     }
     bot.answer_callback_query(q.id).await?;
@@ -625,83 +1433,1280 @@ async fn callback_handler(bot: Bot, q: CallbackQuery, state: Arc<AppState>) -> R
 }
 
 async fn get_portfolio(state: &Arc<AppState>, chat_id: ChatId) -> UserPortfolio {
-    state.portfolios.lock().await.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new() }).clone()
+    state.portfolios.lock().await.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new(), since: Utc::now().timestamp() }).clone()
 }
 
-async fn get_price(symbol: &str) -> f64 {
-    api::get_real_crypto_price(symbol).await.unwrap_or_else(|_| {
-        match symbol { "BTC" => 94000.0, "ETH" => 3400.0, "SOL" => 190.0, "BNB" => 700.0, "ADA" => 0.9, _ => 100.0 }
-    })
+async fn get_price(state: &Arc<AppState>, symbol: &str) -> f64 {
+    match api::get_real_crypto_price(symbol).await {
+        Ok(price) => {
+            record_price(state, "c", symbol, price).await;
+            price
+        }
+        Err(_) => match symbol { "BTC" => 94000.0, "ETH" => 3400.0, "SOL" => 190.0, "BNB" => 700.0, "ADA" => 0.9, _ => 100.0 },
+    }
 }
 
-async fn execute_buy(state: &Arc<AppState>, chat_id: ChatId, symbol: &str) -> Result<String, String> {
-    let price = get_price(symbol).await;
-    let amount = 1000.0;
-    let qty = amount / price;
-    let mut portfolios = state.portfolios.lock().await;
-    let p = portfolios.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new() });
-    if p.balance < amount { return Err("Insufficient balance!".to_string()); }
-    p.balance -= amount;
-    let h = p.holdings.entry(symbol.to_string()).or_insert(Holding { symbol: symbol.to_string(), quantity: 0.0, avg_price: 0.0 });
-    let total_cost = h.quantity * h.avg_price + amount;
-    h.quantity += qty;
-    h.avg_price = total_cost / h.quantity;
-    Ok(format!("✅ <b>BUY ORDER</b>\n\n{}: {:.6} @ ${:.2}\nTotal: ${:.2}\nBalance: ${:.2}", symbol, qty, price, amount, p.balance))
+async fn refresh_issi_cache(state: &Arc<AppState>) -> Result<Vec<api::SyariahApiResponse>, String> {
+    let universe = api::fetch_issi_constituents().await?;
+    *state.issi_cache.lock().await = Some((Utc::now().timestamp(), universe.clone()));
+    Ok(universe)
 }
 
-async fn execute_sell(state: &Arc<AppState>, chat_id: ChatId, symbol: &str) -> Result<String, String> {
-    let price = get_price(symbol).await;
-    let mut portfolios = state.portfolios.lock().await;
-    let p = portfolios.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new() });
-    let h = p.holdings.remove(symbol).ok_or("No holdings for this crypto")?;
-    let value = h.quantity * price;
-    let pnl = value - (h.quantity * h.avg_price);
-    p.balance += value;
-    let emoji = if pnl >= 0.0 { "📈" } else { "📉" };
-    Ok(format!("✅ <b>SELL ORDER</b>\n\n{}: {:.6} @ ${:.2}\nValue: ${:.2}\n{} P&L: ${:.2}\nBalance: ${:.2}", symbol, h.quantity, price, value, emoji, pnl.abs(), p.balance))
+async fn get_issi_universe(state: &Arc<AppState>) -> Result<Vec<api::SyariahApiResponse>, String> {
+    let cached = state.issi_cache.lock().await.clone();
+    match cached {
+        Some((fetched_at, universe)) if Utc::now().timestamp() - fetched_at < ISSI_CACHE_TTL => Ok(universe),
+        stale => refresh_issi_cache(state).await.or_else(|e| stale.map(|(_, u)| u).ok_or(e)),
+    }
 }
 
-fn make_main_menu() -> InlineKeyboardMarkup { 
-    InlineKeyboardMarkup::new(vec![
-        vec![InlineKeyboardButton::callback("🪙 CRYPTO", "menu_crypto"), InlineKeyboardButton::callback("🕌 SAHAM", "menu_sharia")],
-        vec![InlineKeyboardButton::callback("⚡️ SOLANA", "menu_solana"), InlineKeyboardButton::callback("🌡 SENTIMENT", "menu_sentiment_info")],
-        vec![InlineKeyboardButton::callback("🎮 SIMULATOR", "menu_sim_main")],
-        vec![InlineKeyboardButton::callback("🚀 REAL BUY", "menu_solana_real"), InlineKeyboardButton::callback("⭐ WATCHLIST", "menu_watchlist")],
-        vec![InlineKeyboardButton::callback("❓ HELP", "menu_help"), InlineKeyboardButton::callback("🔄 REFRESH", "back_to_main")]
-    ])
+async fn quote_real_buy(state: &AppState, chat_id: ChatId, mint: &str, sol: f64) -> Result<String, String> {
+    let lamports = sol_to_lamports(sol);
+    if lamports == 0 {
+        return Err("Jumlah SOL tidak valid".to_string());
+    }
+    let today = Utc::now().date_naive();
+    swap::SwapLimits::from_env().check(lamports, state.swap_spend.lock().await.spent(today))?;
+
+    let mint_key = solana::parse_pubkey(mint)?;
+    let info = solana::fetch_mint_info(&solana::rpc_client(), &mint_key).await?;
+    let slippage = *state.slippage_bps.lock().await;
+    let quote = swap::fetch_quote(mint, lamports, slippage).await?;
+    let symbol = api::fetch_token_prices(&[mint.to_string()]).await
+        .remove(mint).map(|p| p.0).unwrap_or_else(|| solana::short_address(mint));
+
+    let pending = swap::PendingSwap {
+        mint: mint.to_string(), symbol, decimals: info.decimals, token_program: info.program_id, lamports, quote, created: Utc::now().timestamp(),
+    };
+    let txt = swap::format_quote(&pending);
+    state.pending_swaps.lock().await.insert(chat_id, pending);
+    Ok(txt)
 }
 
-fn make_sim_menu() -> InlineKeyboardMarkup { 
-    InlineKeyboardMarkup::new(vec![
-        vec![InlineKeyboardButton::callback("📈 BUY $1000", "menu_buy"), InlineKeyboardButton::callback("📉 SELL ALL", "menu_sell")],
-        vec![InlineKeyboardButton::callback("💼 PORTFOLIO", "menu_portfolio")],
-        vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
-    ])
+async fn confirm_real_buy(state: &AppState, chat_id: ChatId) -> Result<String, String> {
+    if swap::dry_run_forced() {
+        return dry_run_real_buy(state, chat_id).await;
+    }
+    let pending = state.pending_swaps.lock().await.remove(&chat_id).ok_or_else(|| "Tidak ada quote aktif".to_string())?;
+    if pending.expired(Utc::now().timestamp()) {
+        return Err("Quote kedaluwarsa, minta quote baru".to_string());
+    }
+    let signer = state.signer.as_deref().ok_or_else(|| "Signer belum dikonfigurasi".to_string())?;
+    let client = solana::rpc_client();
+    let (tx, report) = swap::prepare(&client, &pending, signer).await?;
+    // Already HTML, so it is returned as the message rather than as an error to be escaped.
+    if report.err.is_some() {
+        return Ok(format!("❌ <b>Simulasi gagal</b>, transaksi tidak dikirim\n\n{}", swap::format_simulation(&report, &pending.symbol)));
+    }
+    if let Err(e) = swap::verify_simulation(&pending, &report) {
+        return Ok(format!("❌ <b>Simulasi ditolak</b>: {}\nTransaksi tidak dikirim\n\n{}", api::escape_html(&e), swap::format_simulation(&report, &pending.symbol)));
+    }
+
+    // Spend is recorded before broadcasting and never rolled back: a send that times out may still land.
+    // Caps count whichever is larger, the quoted amount or the SOL the simulation actually moved.
+    let spent = pending.lamports.max(report.sol_delta.map(|d| d.unsigned_abs()).unwrap_or(0));
+    let today = Utc::now().date_naive();
+    {
+        let mut spend = state.swap_spend.lock().await;
+        swap::SwapLimits::from_env().check(spent, spend.spent(today))?;
+        spend.record(today, spent);
+    }
+    let signature = swap::send(&client, &tx).await?;
+    Ok(swap::format_receipt(&pending, &signature))
 }
 
-fn make_admin_menu() -> InlineKeyboardMarkup { 
-    InlineKeyboardMarkup::new(vec![
-        vec![InlineKeyboardButton::callback("📊 DASHBOARD", "admin_dashboard"), InlineKeyboardButton::callback("👥 USERS", "admin_users")],
-        vec![InlineKeyboardButton::callback("💰 WALLET", "admin_wallet"), InlineKeyboardButton::callback("📈 ANALYTICS", "admin_analytics")],
-        vec![InlineKeyboardButton::callback("🎁 GIFT PREMIUM", "admin_gift_premium"), InlineKeyboardButton::callback("📩 DM USER", "admin_dm")],
-        vec![InlineKeyboardButton::callback("🚫 BAN", "admin_ban"), InlineKeyboardButton::callback("✅ UNBAN", "admin_unban")],
-        vec![InlineKeyboardButton::callback("📢 BROADCAST", "admin_broadcast"), InlineKeyboardButton::callback("🔧 SYSTEM", "admin_system")],
-        vec![InlineKeyboardButton::callback("➕ ADD GROUP", "admin_add_group"), InlineKeyboardButton::callback("➖ REM GROUP", "admin_rem_group")],
-        vec![InlineKeyboardButton::callback("❌ CLOSE", "back_to_main")]
-    ])
+async fn dry_run_real_buy(state: &AppState, chat_id: ChatId) -> Result<String, String> {
+    let pending = state.pending_swaps.lock().await.get(&chat_id).cloned().ok_or_else(|| "Tidak ada quote aktif".to_string())?;
+    let signer = state.signer.as_deref().ok_or_else(|| "Signer belum dikonfigurasi".to_string())?;
+    let (_, report) = swap::prepare(&solana::rpc_client(), &pending, signer).await?;
+    Ok(swap::format_simulation(&report, &pending.symbol))
 }
 
-fn make_sentiment_menu() -> InlineKeyboardMarkup {
-    InlineKeyboardMarkup::new(vec![
-        vec![InlineKeyboardButton::callback("🎭 Fear & Greed", "sentiment_fng"), InlineKeyboardButton::callback("📈 Market Pulse", "sentiment_pulse")],
-        vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
-    ])
+async fn admin_wallet_report(signer: &dyn signer::TxSigner) -> String {
+    match solana::fetch_hot_wallet(&signer.pubkey()).await {
+        Ok(wallet) => {
+            let mut mints: Vec<String> = wallet.overview.tokens.iter().filter(|t| t.amount > 0.0).map(|t| t.mint.clone()).collect();
+            mints.push(solana::WSOL_MINT.to_string());
+            let prices = api::fetch_token_prices(&mints).await;
+            solana::format_hot_wallet(&wallet, signer.source(), swap::dry_run_forced(), &prices)
+        }
+        Err(e) => format!("💰 <b>BOT WALLET</b>\n━━━━━━━━━━━━━━━━━━━━━━━\n├ 🔑 Pubkey: <code>{}</code>\n└ ❌ {}", signer.pubkey(), api::escape_html(&e)),
+    }
+}
+
+async fn staking_report(address: &str) -> String {
+    let sol = [solana::WSOL_MINT.to_string()];
+    let (overview, prices) = tokio::join!(staking::fetch_staking(address), api::fetch_token_prices(&sol));
+    match overview {
+        Ok(overview) => staking::format_staking(&overview, prices.get(solana::WSOL_MINT).map(|p| p.1)),
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+async fn holders_report(mint: &str) -> String {
+    let mints = [mint.to_string()];
+    let (report, prices) = tokio::join!(holders::fetch_holder_report(mint), api::fetch_token_prices(&mints));
+    match report {
+        Ok(report) => holders::format_holder_report(&report, prices.get(&report.mint)),
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+async fn send_holders_pie(bot: &Bot, chat_id: ChatId, mint: &str) -> ResponseResult<()> {
+    let report = match holders::fetch_holder_report(mint).await {
+        Ok(r) => r,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    let slices = report.pie_slices();
+    let png = match chart::render_pie_png(&slices.iter().map(|s| s.1).collect::<Vec<f64>>()) {
+        Ok(p) => p,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    let legend: Vec<String> = slices.iter().enumerate()
+        .map(|(i, (name, share))| format!("{} <b>{}</b>: <code>{:.2}%</code>", chart::SERIES_LEGEND[i % chart::SERIES_LEGEND.len()], api::escape_html(name), share))
+        .collect();
+    let caption = format!(
+        "👥 <b>HOLDER DISTRIBUTION</b>\n========================\n{}\n========================\n<i>💡 Gini (top {}): {:.3}</i>",
+        legend.join("\n"), report.holders.len(), report.gini()
+    );
+    bot.send_photo(chat_id, InputFile::memory(png).file_name("holders.png")).caption(caption).parse_mode(ParseMode::Html).reply_markup(make_back_menu()).await?;
+    Ok(())
+}
+
+async fn solana_status_report() -> String {
+    let sol = [solana::WSOL_MINT.to_string()];
+    let (status, prices) = tokio::join!(solana::fetch_network_status(), api::fetch_token_prices(&sol));
+    match status {
+        Ok(status) => solana::format_network_status(&status, prices.get(solana::WSOL_MINT).map(|p| p.1)),
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+async fn wallet_report(address: &str) -> String {
+    match solana::fetch_wallet(address).await {
+        Ok(wallet) => {
+            let mut mints: Vec<String> = wallet.tokens.iter().filter(|t| t.amount > 0.0).map(|t| t.mint.clone()).collect();
+            mints.push(solana::WSOL_MINT.to_string());
+            let prices = api::fetch_token_prices(&mints).await;
+            solana::format_wallet(&wallet, &prices)
+        }
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+async fn format_feed_status(state: &AppState, chat_id: ChatId) -> String {
+    let subscribed = state.feed_subscribers.lock().await.contains(&chat_id);
+    let minutes: u64 = std::env::var("FEED_INTERVAL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+    format!(
+        "📡 <b>SOLANA PAIR FEED</b>\n========================\n• Status: {}\n• Interval: <code>{} menit</code>\n\n🔎 <b>FILTER:</b>\n{}\n========================\n<i>💡 Pair trending & baru dari DexScreener, tiap pair hanya diumumkan sekali</i>",
+        if subscribed { "🟢 Berlangganan" } else { "⚪️ Tidak berlangganan" }, minutes.max(5), feed::FeedFilter::from_env().describe()
+    )
+}
+
+async fn run_feed(bot: &Bot, state: &Arc<AppState>) {
+    let recipients: HashSet<ChatId> = state.feed_subscribers.lock().await.clone();
+    if recipients.is_empty() { return; }
+
+    let filter = feed::FeedFilter::from_env();
+    let now = Utc::now().timestamp();
+    // Pairs announced longer ago than the age window can no longer pass the filter, so they are forgotten.
+    let seen = {
+        let mut seen = state.feed_seen.lock().await;
+        seen.retain(|_, at| ((now - *at) as f64) < filter.max_age_hours * 3600.0);
+        seen.clone()
+    };
+    let items = match feed::build_digest(&filter, &seen).await {
+        Ok(items) => items,
+        Err(e) => { log::warn!("Feed failed: {}", e); return; }
+    };
+    if items.is_empty() { return; }
+    state.feed_seen.lock().await.extend(items.iter().map(|i| (i.pair.pair_address.clone(), now)));
+
+    let digest = feed::format_digest(&items);
+    for chat in recipients {
+        let _ = bot.send_message(chat, digest.clone()).parse_mode(ParseMode::Html).disable_web_page_preview(true).await;
+    }
+}
+
+async fn add_wallet_watch(state: &AppState, chat_id: ChatId, address: &str) -> String {
+    let address = match solana::parse_pubkey(address) {
+        Ok(pk) => pk.to_string(),
+        Err(e) => return format!("❌ {}", e),
+    };
+    let mut watches = state.wallet_watches.lock().await;
+    let list = watches.entry(chat_id).or_insert_with(Vec::new);
+    if list.contains(&address) {
+        return format!("ℹ️ <code>{}</code> sudah dipantau", address);
+    }
+    if list.len() >= MAX_WATCHED_WALLETS {
+        return format!("❌ Maksimal {} wallet dipantau", MAX_WATCHED_WALLETS);
+    }
+    list.push(address.clone());
+    format!("✅ <code>{}</code> dipantau!\n🔔 Kamu akan mendapat notifikasi transfer & swap.", address)
+}
+
+async fn remove_wallet_watch(state: &AppState, chat_id: ChatId, address: &str) -> bool {
+    let mut watches = state.wallet_watches.lock().await;
+    let Some(list) = watches.get_mut(&chat_id) else { return false };
+    let before = list.len();
+    list.retain(|a| a != address.trim());
+    before != list.len()
+}
+
+async fn format_wallet_watches(state: &AppState, chat_id: ChatId) -> String {
+    let list = state.wallet_watches.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let mut txt = format!("👁 <b>WALLET WATCH</b> ({}/{})\n========================\n", list.len(), MAX_WATCHED_WALLETS);
+    if list.is_empty() {
+        txt.push_str("<i>Belum ada wallet dipantau</i>\n");
+    }
+    for a in &list {
+        txt.push_str(&format!("• <code>{}</code>\n", a));
+    }
+    txt.push_str("========================\n<i>💡 Tambah: /watchwallet ADDRESS\nHapus: /unwatchwallet ADDRESS</i>");
+    txt
+}
+
+async fn poll_wallet_watches(bot: &Bot, state: &Arc<AppState>) {
+    let watches = state.wallet_watches.lock().await.clone();
+    let mut subscribers: HashMap<String, Vec<ChatId>> = HashMap::new();
+    for (chat, list) in &watches {
+        for address in list {
+            subscribers.entry(address.clone()).or_default().push(*chat);
+        }
+    }
+    state.wallet_cursors.lock().await.retain(|a, _| subscribers.contains_key(a));
+    if subscribers.is_empty() { return; }
+
+    let client = solana::rpc_client();
+    for (address, chats) in subscribers {
+        let Ok(owner) = solana::parse_pubkey(&address) else { continue };
+        let cursor = state.wallet_cursors.lock().await.get(&address).cloned();
+        // Page backwards with `before` until the cursor is reached, so bursts are not silently dropped.
+        let mut signatures = Vec::new();
+        let mut before = None;
+        let mut failed = false;
+        for _ in 0..WALLET_POLL_MAX_PAGES {
+            let config = solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config {
+                limit: Some(if cursor.is_some() { WALLET_POLL_PAGE } else { 1 }),
+                before,
+                until: cursor.as_deref().and_then(|c| c.parse().ok()),
+                ..Default::default()
+            };
+            match client.get_signatures_for_address_with_config(&owner, config).await {
+                Ok(page) => {
+                    let done = cursor.is_none() || page.len() < WALLET_POLL_PAGE;
+                    before = page.last().and_then(|s| s.signature.parse().ok());
+                    signatures.extend(page);
+                    if done { break; }
+                }
+                Err(e) => { log::warn!("Wallet watch {} failed: {}", address, e); failed = true; break; }
+            }
+        }
+        // Keep the old cursor when paging broke off, the next poll retries the whole gap.
+        if failed { continue; }
+        let Some(newest) = signatures.first() else { continue };
+        state.wallet_cursors.lock().await.insert(address.clone(), newest.signature.clone());
+        // First poll only sets the cursor so subscribing does not replay old history.
+        if cursor.is_none() { continue; }
+
+        let successful: Vec<_> = signatures.iter().filter(|s| s.err.is_none()).collect();
+        let skipped = successful.len().saturating_sub(WALLET_POLL_PAGE);
+        if skipped > 0 {
+            let txt = format!(
+                "👛 <b>{}</b>: <i>+{} transaksi lainnya sejak cek terakhir</i> • <a href=\"https://solscan.io/account/{}\">Solscan</a>",
+                solana::short_address(&address), skipped, address
+            );
+            for chat in &chats {
+                let _ = bot.send_message(*chat, txt.clone()).parse_mode(ParseMode::Html).disable_web_page_preview(true).await;
+            }
+        }
+        // Only the newest transactions are decoded, oldest first.
+        for sig in successful.into_iter().take(WALLET_POLL_PAGE).rev() {
+            let tx = match txdecode::fetch_transaction(&client, &sig.signature).await {
+                Ok(tx) => tx,
+                Err(e) => { log::warn!("Decode {} failed: {}", sig.signature, e); continue; }
+            };
+            let mut mints: Vec<String> = tx.changes_for(&address).iter().filter_map(|c| c.mint.clone()).collect();
+            mints.push(solana::WSOL_MINT.to_string());
+            let prices = api::fetch_token_prices(&mints).await;
+            let Some(txt) = txdecode::format_activity(&address, &tx, &prices) else { continue };
+            for chat in &chats {
+                let _ = bot.send_message(*chat, txt.clone()).parse_mode(ParseMode::Html).disable_web_page_preview(true).await;
+            }
+        }
+    }
+}
+
+async fn import_document(bot: &Bot, state: &Arc<AppState>, chat_id: ChatId, doc: &teloxide::types::Document, preset: Option<csvio::Preset>) -> (String, bool) {
+    if doc.file.size > csvio::MAX_IMPORT_BYTES {
+        return ("❌ File terlalu besar (maks 1 MB)".to_string(), false);
+    }
+    let mut bytes = Vec::new();
+    let downloaded = match bot.get_file(&doc.file.id).await {
+        Ok(file) => bot.download_file(&file.path, &mut bytes).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = downloaded {
+        return (format!("❌ Gagal mengunduh file: {}", e), false);
+    }
+    let Ok(text) = String::from_utf8(bytes) else {
+        return ("❌ File harus CSV teks (UTF-8)".to_string(), false);
+    };
+    let ledger = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    match csvio::import_trades(&text, preset, &ledger) {
+        Ok(report) => {
+            let has_trades = !report.trades.is_empty();
+            let txt = csvio::format_import_report(&report);
+            state.pending_imports.lock().await.insert(chat_id, report.trades);
+            (txt, has_trades)
+        }
+        Err(e) => (format!("❌ {}\n\n{}", e, csvio::format_presets()), false),
+    }
+}
+
+async fn export_table(state: &Arc<AppState>, chat_id: ChatId, what: &str) -> Result<csvio::Table, String> {
+    match what {
+        "trades" => Ok(csvio::trades_table(&state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default())),
+        "ledger" => csvio::positions_table(&state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default()),
+        "watchlist" => Ok(csvio::watchlist_table(&state.watchlist.lock().await.get(&chat_id).cloned().unwrap_or_default())),
+        "sim" => {
+            let portfolio = get_portfolio(state, chat_id).await;
+            let mut rows: Vec<Vec<serde_json::Value>> = portfolio.holdings.values()
+                .map(|h| vec![
+                    serde_json::json!(h.symbol), serde_json::json!(h.quantity), serde_json::json!(h.avg_price),
+                    serde_json::json!(chrono::DateTime::from_timestamp(h.since, 0).map(|d| d.date_naive().to_string())),
+                ])
+                .collect();
+            rows.push(vec![serde_json::json!("USD"), serde_json::json!(portfolio.balance), serde_json::json!(1.0), serde_json::Value::Null]);
+            Ok(csvio::Table { name: "simulator", headers: vec!["symbol", "quantity", "avg_price_usd", "since"], rows })
+        }
+        _ => Err("Data: trades, ledger, sim, watchlist".to_string()),
+    }
+}
+
+async fn send_export(bot: &Bot, state: &Arc<AppState>, chat_id: ChatId, what: &str, format: csvio::ExportFormat) -> ResponseResult<()> {
+    match export_table(state, chat_id, what).await {
+        Ok(table) => {
+            let caption = format!("📤 <b>{}</b> • {} baris • <code>{}</code>", table.name.to_uppercase(), table.rows.len(), format.code().to_uppercase());
+            let file = InputFile::memory(table.render(format)).file_name(table.file_name(format));
+            bot.send_document(chat_id, file).caption(caption).parse_mode(ParseMode::Html).await?;
+        }
+        Err(e) => { bot.send_message(chat_id, format!("❌ {}", e)).reply_markup(make_export_menu()).await?; }
+    }
+    Ok(())
+}
+
+async fn set_rebalance_targets(state: &Arc<AppState>, chat_id: ChatId, text: &str) -> String {
+    match rebalance::parse_targets(text) {
+        Ok(targets) => {
+            let txt = format!("✅ <b>TARGET DISIMPAN</b>\n\n{}\n<i>Pilih portofolio untuk dihitung</i>", rebalance::format_targets(&targets));
+            state.rebalance_targets.lock().await.insert(chat_id, targets);
+            // A plan computed for the old targets must not be executable anymore.
+            state.pending_rebalances.lock().await.remove(&chat_id);
+            txt
+        }
+        Err(e) => format!("❌ {}\n\n{}", e, REBALANCE_PROMPT),
+    }
+}
+
+async fn rebalance_holdings(state: &Arc<AppState>, chat_id: ChatId, venue: rebalance::Venue, targets: &[rebalance::Target]) -> Result<rebalance::Holdings, String> {
+    match venue {
+        rebalance::Venue::Simulator => {
+            let portfolio = get_portfolio(state, chat_id).await;
+            let mut assets = Vec::new();
+            for h in portfolio.holdings.values() {
+                assets.push(rebalance::Asset { class: ledger::AssetClass::Crypto, symbol: h.symbol.clone(), quantity: h.quantity, price: Some(get_price(state, &h.symbol).await) });
+            }
+            // Coins targeted by symbol but not held yet still need a price to size the buy.
+            for t in targets {
+                if let rebalance::Bucket::Asset(symbol) = &t.bucket {
+                    if !portfolio.holdings.contains_key(symbol) {
+                        assets.push(rebalance::Asset { class: ledger::AssetClass::Crypto, symbol: symbol.clone(), quantity: 0.0, price: Some(get_price(state, symbol).await) });
+                    }
+                }
+            }
+            Ok(rebalance::Holdings { venue, cash: portfolio.balance, assets })
+        }
+        rebalance::Venue::Ledger => {
+            let book = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+            let replay = book.replay().map_err(|e| format!("Ledger tidak konsisten: {}", e))?;
+            let quotes = ledger_quotes(state, &replay).await;
+            let open = replay.positions.iter().filter(|p| p.is_open());
+            let cash = open.clone().filter(|p| p.class == ledger::AssetClass::Cash).map(|p| p.quantity).sum();
+            let assets = open.filter(|p| p.class != ledger::AssetClass::Cash)
+                .map(|p| rebalance::Asset { class: p.class, symbol: p.symbol.clone(), quantity: p.quantity, price: quotes.get(&p.key()).and_then(|q| q.price) })
+                .collect();
+            Ok(rebalance::Holdings { venue, cash, assets })
+        }
+    }
+}
+
+async fn rebalance_report(state: &Arc<AppState>, chat_id: ChatId, venue: rebalance::Venue) -> (String, bool) {
+    let targets = state.rebalance_targets.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let holdings = match rebalance_holdings(state, chat_id, venue, &targets).await {
+        Ok(h) => h,
+        Err(e) => return (format!("❌ {}", e), false),
+    };
+    let plan = rebalance::build_plan(&holdings, &targets);
+    let executable = venue == rebalance::Venue::Simulator && !plan.orders.is_empty();
+    if executable {
+        state.pending_rebalances.lock().await.insert(chat_id, plan.orders.clone());
+    }
+    (rebalance::format_plan(&plan), executable)
+}
+
+// Sells first so their proceeds fund the buys; buys re-check the syariah filter and shrink to the remaining balance.
+// Orders keep the planned quantities but fill at prices fetched now, not the ones shown in the plan.
+async fn execute_rebalance(state: &Arc<AppState>, chat_id: ChatId, mut orders: Vec<rebalance::Order>) -> String {
+    for o in orders.iter_mut() {
+        o.price = get_price(state, &o.symbol).await;
+    }
+    let mut blocked = Vec::new();
+    for o in orders.iter().filter(|o| o.side == ledger::Side::Buy) {
+        if let Err(e) = check_coin_filter(state, chat_id, &o.symbol).await {
+            blocked.push((o.symbol.clone(), e));
+        }
+    }
+    let mut portfolios = state.portfolios.lock().await;
+    let p = portfolios.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new(), since: Utc::now().timestamp() });
+    let mut lines = Vec::new();
+    for o in orders.iter().filter(|o| o.side == ledger::Side::Sell) {
+        let Some(h) = p.holdings.get_mut(&o.symbol) else { continue };
+        let qty = o.quantity.min(h.quantity);
+        h.quantity -= qty;
+        if h.quantity <= 1e-9 {
+            p.holdings.remove(&o.symbol);
+        }
+        p.balance += qty * o.price;
+        lines.push(format!("├ 🔴 JUAL <b>{}</b> {:.6} @ ${:.2} = ${:.2}", o.symbol, qty, o.price, qty * o.price));
+    }
+    for o in orders.iter().filter(|o| o.side == ledger::Side::Buy && !blocked.iter().any(|b| b.0 == o.symbol)) {
+        let amount = o.value().min(p.balance);
+        if amount <= 0.0 { continue; }
+        let qty = amount / o.price;
+        p.balance -= amount;
+        let h = p.holdings.entry(o.symbol.clone()).or_insert(Holding { symbol: o.symbol.clone(), quantity: 0.0, avg_price: 0.0, since: Utc::now().timestamp() });
+        let total_cost = h.quantity * h.avg_price + amount;
+        h.quantity += qty;
+        h.avg_price = total_cost / h.quantity;
+        lines.push(format!("├ 🟢 BELI <b>{}</b> {:.6} @ ${:.2} = ${:.2}", o.symbol, qty, o.price, amount));
+    }
+    let mut out = format!("✅ <b>REBALANCE DIEKSEKUSI</b>\n\n{}\n└ 💵 Balance: <code>${:.2}</code>", lines.join("\n"), p.balance);
+    for (_, e) in blocked {
+        out.push_str(&format!("\n\n⚠️ Dilewati: {}", api::escape_html(&e)));
+    }
+    out
+}
+
+async fn tax_report(state: &Arc<AppState>, chat_id: ChatId, year: i32) -> String {
+    let book = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    match tax::build_report(&book, year) {
+        Ok(report) => tax::format_report(&report),
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+async fn send_tax_export(bot: &Bot, state: &Arc<AppState>, chat_id: ChatId, year: i32, kind: &str) -> ResponseResult<()> {
+    let book = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let report = match tax::build_report(&book, year) {
+        Ok(r) => r,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    let (bytes, name) = if kind == "txt" {
+        (tax::report_text(&report).into_bytes(), format!("lubix_pajak_{}.txt", year))
+    } else {
+        let table = tax::report_table(&report);
+        (table.render(csvio::ExportFormat::Csv), format!("lubix_pajak_{}.csv", year))
+    };
+    let caption = format!("💼 <b>PAJAK {}</b> • {} transaksi • <code>{}</code>", year, report.lines.len(), kind.to_uppercase());
+    bot.send_document(chat_id, InputFile::memory(bytes).file_name(name)).caption(caption).parse_mode(ParseMode::Html).await?;
+    Ok(())
+}
+
+async fn record_ledger_trade(state: &Arc<AppState>, chat_id: ChatId, text: &str) -> String {
+    let trade = match ledger::parse_trade(text) {
+        Ok(t) => t,
+        Err(e) => return format!("❌ {}\n\n{}", e, LEDGER_PROMPT),
+    };
+    let mut ledgers = state.ledgers.lock().await;
+    let book = ledgers.entry(chat_id).or_default();
+    book.trades.push(trade.clone());
+    // A sell must be covered by what was held on that date.
+    if let Err(e) = book.replay() {
+        book.trades.pop();
+        return format!("❌ {}", e);
+    }
+    format!(
+        "✅ <b>TERCATAT</b>\n\n{} {} {} <b>{}</b>\n• Jumlah: <code>{}</code>\n• Harga: <code>Rp {}</code>\n• Fee: <code>Rp {}</code>\n• Tanggal: <code>{}</code>",
+        if trade.side == ledger::Side::Buy { "🟢 BELI" } else { "🔴 JUAL" }, trade.class.emoji(), trade.class.label(),
+        api::escape_html(&trade.symbol), trade.quantity, format_angka(trade.price), format_angka(trade.fee), trade.date
+    )
+}
+
+async fn remove_ledger_trade(state: &Arc<AppState>, chat_id: ChatId, number: usize) -> String {
+    let mut ledgers = state.ledgers.lock().await;
+    let book = ledgers.entry(chat_id).or_default();
+    if number == 0 || number > book.trades.len() {
+        return "❌ Nomor transaksi tidak ditemukan".to_string();
+    }
+    let removed = book.trades.remove(number - 1);
+    if let Err(e) = book.replay() {
+        book.trades.insert(number - 1, removed);
+        return format!("❌ Tidak bisa dihapus: {}", e);
+    }
+    format!("🗑 Transaksi #{} ({} {}) dihapus", number, removed.class.code(), api::escape_html(&removed.symbol))
+}
+
+async fn set_ledger_method(state: &Arc<AppState>, chat_id: ChatId, method: ledger::CostMethod) -> String {
+    state.ledgers.lock().await.entry(chat_id).or_default().method = method;
+    format!("⚙️ Metode cost basis: <code>{}</code>", method.label())
+}
+
+fn coin_compliance(status: crypto_syariah::CoinStatus) -> ledger::Compliance {
+    match status {
+        crypto_syariah::CoinStatus::Permissible => ledger::Compliance::Syariah,
+        crypto_syariah::CoinStatus::Doubtful => ledger::Compliance::Doubtful,
+        crypto_syariah::CoinStatus::NonCompliant => ledger::Compliance::NonSyariah,
+    }
+}
+
+async fn ledger_quotes(state: &Arc<AppState>, replay: &ledger::Replay) -> HashMap<String, ledger::Quote> {
+    let open: Vec<&ledger::Position> = replay.positions.iter().filter(|p| p.is_open()).collect();
+    let mints: Vec<String> = open.iter().filter(|p| p.class == ledger::AssetClass::Token).map(|p| p.symbol.clone()).collect();
+    let token_prices = if mints.is_empty() { HashMap::new() } else { api::fetch_token_prices(&mints).await };
+
+    let mut quotes = HashMap::new();
+    for p in open {
+        let quote = match p.class {
+            ledger::AssetClass::Cash => ledger::Quote { name: None, price: Some(1.0), compliance: ledger::Compliance::Syariah },
+            ledger::AssetClass::Stock => match api::fetch_stock_data(&p.symbol).await {
+                Ok(stock) => {
+                    record_stock_price(state, &stock).await;
+                    ledger::Quote {
+                        name: None,
+                        price: stock.harga.as_ref().and_then(|h| h.now).map(|n| n as f64),
+                        compliance: match stock.issi {
+                            Some(true) => ledger::Compliance::Syariah,
+                            Some(false) => ledger::Compliance::NonSyariah,
+                            None => ledger::Compliance::Unknown,
+                        },
+                    }
+                }
+                Err(_) => ledger::Quote { name: None, price: None, compliance: ledger::Compliance::Unknown },
+            },
+            ledger::AssetClass::Crypto => ledger::Quote {
+                name: None,
+                price: api::get_real_crypto_price(&p.symbol).await.ok().map(|usd| usd * api::USD_IDR),
+                compliance: coin_compliance(get_coin_rule(state, &p.symbol).await.status),
+            },
+            ledger::AssetClass::Token => {
+                let found = token_prices.get(&p.symbol);
+                let symbol = found.map(|t| t.0.clone());
+                // Keyed on the mint: a DEX symbol like "BTC" must not inherit the curated coin's status.
+                let compliance = match state.coin_rules.lock().await.get(&p.symbol.to_uppercase()) {
+                    Some(rule) => coin_compliance(rule.status),
+                    None => ledger::Compliance::Unknown,
+                };
+                ledger::Quote { name: symbol, price: found.map(|t| t.1 * api::USD_IDR), compliance }
+            }
+        };
+        quotes.insert(p.key(), quote);
+    }
+    quotes
+}
+
+async fn ledger_report(state: &Arc<AppState>, chat_id: ChatId) -> String {
+    let book = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    match book.replay() {
+        Ok(replay) => {
+            let quotes = ledger_quotes(state, &replay).await;
+            ledger::format_portfolio(&book, &replay, &quotes)
+        }
+        Err(e) => format!("❌ Ledger tidak konsisten: {}", e),
+    }
+}
+
+async fn update_zakat_settings(state: &Arc<AppState>, chat_id: ChatId, args: &str) -> Result<(), String> {
+    // Parse every token before touching the settings so a typo does not leave a half-applied update.
+    let mut gold_price = None;
+    let mut cash = None;
+    for token in args.split_whitespace() {
+        let (key, value) = token.split_once('=').ok_or_else(|| format!("Format salah: {}", token))?;
+        match key.to_lowercase().as_str() {
+            "emas" if value.eq_ignore_ascii_case("auto") => gold_price = Some(None),
+            "emas" => gold_price = Some(Some(ledger::parse_number(value)?)),
+            "kas" => {
+                let (value, date) = value.split_once('@').unwrap_or((value, ""));
+                let since = if date.is_empty() {
+                    None
+                } else {
+                    Some(chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| format!("Tanggal tidak valid: {}", date))?
+                        .and_hms_opt(0, 0, 0).map(|d| d.and_utc().timestamp()).unwrap_or_default())
+                };
+                cash = Some((ledger::parse_number(value)?, since));
+            }
+            _ => return Err(format!("Parameter tidak dikenal: {}", key)),
+        }
+    }
+
+    let mut all = state.zakat_settings.lock().await;
+    let settings = all.entry(chat_id).or_default();
+    if let Some(price) = gold_price {
+        settings.gold_price = price;
+    }
+    if let Some((amount, since)) = cash {
+        // Updating the balance without a date keeps the haul running from the original date.
+        let since = since.or(settings.cash.map(|c| c.1)).unwrap_or_else(|| Utc::now().timestamp());
+        settings.cash = Some((amount, since));
+    }
+    Ok(())
+}
+
+// Real holdings (manual cash plus the ledger) by default; `sim` assesses the play-money simulator on its own.
+async fn build_zakat_report(state: &Arc<AppState>, chat_id: ChatId, sim: bool) -> String {
+    let settings = state.zakat_settings.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let (gold_price, gold_source) = match settings.gold_price {
+        Some(p) => (p, "manual"),
+        None => match api::fetch_gold_price_idr().await {
+            Ok(p) => (p, "live"),
+            Err(e) => return format!("❌ {}\n\nSet harga emas manual: /zakat emas=1450000", e),
+        },
+    };
+
+    let mut assets = Vec::new();
+    if sim {
+        if let Some(portfolio) = state.portfolios.lock().await.get(&chat_id).cloned() {
+            for (sym, h) in &portfolio.holdings {
+                let price = get_price(state, sym).await;
+                assets.push(zakat::ZakatAsset { label: format!("{} (sim)", sym), class: "🪙", value_idr: h.quantity * price * api::USD_IDR, since: h.since });
+            }
+            assets.push(zakat::ZakatAsset { label: "Cash (sim)".to_string(), class: "💵", value_idr: portfolio.balance * api::USD_IDR, since: portfolio.since });
+        }
+        let report = zakat::ZakatReport { gold_price, assets, now: Utc::now().timestamp() };
+        return zakat::format_report(&report, gold_source, "🎮 SIMULATOR");
+    }
+    if let Some((amount, since)) = settings.cash {
+        assets.push(zakat::ZakatAsset { label: "Kas IDR".to_string(), class: "💵", value_idr: amount, since });
+    }
+    let book = state.ledgers.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    if let Ok(replay) = book.replay() {
+        let quotes = ledger_quotes(state, &replay).await;
+        for v in ledger::value_positions(&replay, &quotes) {
+            let since = v.position.since.and_then(|d| d.and_hms_opt(0, 0, 0)).map(|d| d.and_utc().timestamp()).unwrap_or_else(|| Utc::now().timestamp());
+            assets.push(zakat::ZakatAsset { label: format!("{} (riil)", v.name()), class: v.position.class.emoji(), value_idr: v.value(), since });
+        }
+    }
+
+    let report = zakat::ZakatReport { gold_price, assets, now: Utc::now().timestamp() };
+    zakat::format_report(&report, gold_source, "📒 RIIL (kas + ledger)")
+}
+
+async fn purification_holdings(state: &Arc<AppState>, chat_id: ChatId, sim: bool) -> HashMap<String, f64> {
+    if sim {
+        return state.portfolios.lock().await.get(&chat_id)
+            .map(|p| p.holdings.values()
+                .filter(|h| h.quantity > 0.0)
+                .map(|h| (h.symbol.to_uppercase(), h.quantity))
+                .collect())
+            .unwrap_or_default();
+    }
+    state.ledgers.lock().await.get(&chat_id)
+        .and_then(|b| b.replay().ok())
+        .map(|r| r.positions.into_iter()
+            .filter(|p| p.class == ledger::AssetClass::Stock && p.is_open())
+            .map(|p| (p.symbol, p.quantity))
+            .collect())
+        .unwrap_or_default()
+}
+
+async fn calculate_purification(state: &Arc<AppState>, chat_id: ChatId, text: &str) -> (String, bool) {
+    let (opts, text) = match purification::parse_options(text, Utc::now().year()) {
+        Ok(v) => v,
+        Err(e) => return (format!("❌ {}\n\n{}", e, TAZKIYAH_PROMPT), false),
+    };
+    let holdings = purification_holdings(state, chat_id, opts.sim).await;
+    if opts.whole && text.trim().is_empty() {
+        if holdings.is_empty() {
+            return (format!("❌ Belum ada saham di portofolio\n\n{}", TAZKIYAH_PROMPT), false);
+        }
+        return (purification::format_template(&holdings, opts.sim), false);
+    }
+    let parsed = if opts.whole {
+        purification::parse_whole_portfolio(text, &holdings)
+    } else {
+        purification::parse_entries(text, &holdings).map(|e| (e, Vec::new()))
+    };
+    let (entries, skipped) = match parsed {
+        Ok(v) => v,
+        Err(e) => return (format!("❌ {}\n\n{}", e, TAZKIYAH_PROMPT), false),
+    };
+    let year = opts.year.unwrap_or_else(|| Utc::now().year());
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for entry in entries {
+        let ratio = api::fetch_stock_data(&entry.ticker).await
+            .map(|s| s.syariah_indicator.as_ref().and_then(|i| syariah::indicator_value(&i.non_halal)));
+        match ratio {
+            Ok(Some(non_halal)) => records.push(purification::PurificationRecord {
+                year, ticker: entry.ticker, shares: entry.shares, dividend_per_share: entry.dividend_per_share, non_halal,
+            }),
+            Ok(None) => errors.push(format!("{} - rasio non-halal tidak tersedia", entry.ticker)),
+            Err(e) => errors.push(e),
+        }
+    }
+    let txt = purification::format_result(&records, &errors, &skipped);
+    let has_records = !records.is_empty();
+    state.pending_purifications.lock().await.insert(chat_id, records);
+    (txt, has_records)
+}
+
+async fn run_issi_snapshot(bot: &Bot, state: &Arc<AppState>) {
+    // Watched stocks plus stocks still held in the ledger; crypto watchlists are not ISSI-screened.
+    let mut watchers: HashMap<String, HashSet<ChatId>> = HashMap::new();
+    for (chat, tickers) in state.stock_watches.lock().await.iter() {
+        for t in tickers { watchers.entry(t.to_uppercase()).or_default().insert(*chat); }
+    }
+    for (chat, book) in state.ledgers.lock().await.iter() {
+        let Ok(replay) = book.replay() else { continue };
+        for p in replay.positions.iter().filter(|p| p.class == ledger::AssetClass::Stock && p.is_open()) {
+            watchers.entry(p.symbol.to_uppercase()).or_default().insert(*chat);
+        }
+    }
+
+    for (ticker, chats) in watchers {
+        let stock = match api::fetch_stock_data(&ticker).await {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        record_stock_price(state, &stock).await;
+        let current = syariah::Snapshot::from_stock(&stock);
+        let previous = state.issi_snapshots.lock().await.insert(ticker.clone(), current.clone());
+        let changes = match previous {
+            Some(prev) => syariah::diff(&prev, &current),
+            None => continue,
+        };
+        if changes.is_empty() { continue; }
+
+        let alert = format!(
+            "🔔 <b>ISSI ALERT - {}</b>\n========================\n{}\n========================\n<i>💡 Perubahan sejak snapshot sebelumnya</i>",
+            ticker, changes.join("\n")
+        );
+        for chat in chats {
+            let _ = bot.send_message(chat, alert.clone()).parse_mode(ParseMode::Html).await;
+        }
+    }
+}
+
+async fn render_screener(state: &Arc<AppState>, chat_id: ChatId, page: usize) -> Result<(String, InlineKeyboardMarkup), String> {
+    let universe = get_issi_universe(state).await?;
+    let filter = state.screener_filters.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let rows = screener::apply(&universe, &filter);
+    let page = page.min(screener::page_count(rows.len()) - 1);
+    Ok((screener::format_page(&rows, &filter, page), make_screener_menu(&filter, page, screener::page_count(rows.len()))))
+}
+
+// Every price the bot sees is kept per chart kind ("c", "s", "t") as a fallback when history APIs fail.
+async fn record_price(state: &Arc<AppState>, kind: &str, symbol: &str, price: f64) {
+    if price <= 0.0 { return; }
+    let mut history = state.price_history.lock().await;
+    let points = history.entry(format!("{}:{}", kind, symbol.to_uppercase())).or_default();
+    points.push_back(chart::Candle::point(Utc::now().timestamp(), price));
+    if points.len() > 5000 { points.pop_front(); }
+}
+
+async fn record_stock_price(state: &Arc<AppState>, stock: &api::SyariahApiResponse) {
+    if let Some(now) = stock.harga.as_ref().and_then(|h| h.now) {
+        record_price(state, "s", &stock.code, now as f64).await;
+    }
+}
+
+async fn record_dex_price(state: &Arc<AppState>, pair: &api::DexPairData) {
+    if let Some(price) = pair.price_usd.as_deref().and_then(|p| p.parse::<f64>().ok()) {
+        record_price(state, "t", &api::dex_chart_symbol(pair), price).await;
+    }
+}
+
+async fn stock_card(state: &Arc<AppState>, ticker: &str, standard: syariah::Standard) -> Result<String, String> {
+    let stock = api::fetch_stock_data(ticker).await?;
+    record_stock_price(state, &stock).await;
+    Ok(api::format_stock_card(&stock, standard))
+}
+
+async fn load_chart_candles(state: &Arc<AppState>, kind: &str, symbol: &str, range: chart::ChartRange) -> Result<(Vec<chart::Candle>, &'static str), String> {
+    let fetched = match kind {
+        "c" => api::fetch_crypto_history(symbol, range).await.map(|c| (c, "Binance")),
+        "s" => api::fetch_stock_history(symbol, range).await.map(|c| (c, "Yahoo Finance")),
+        "t" => api::fetch_dex_history(symbol, range).await.map(|c| (c, "GeckoTerminal")),
+        _ => Err("Unknown chart type".to_string()),
+    };
+    match fetched {
+        Ok((candles, source)) if candles.len() >= 2 => Ok((candles, source)),
+        other => {
+            let since = Utc::now().timestamp() - range.seconds();
+            let recorded: Vec<chart::Candle> = state.price_history.lock().await
+                .get(&format!("{}:{}", kind, symbol.to_uppercase()))
+                .map(|pts| pts.iter().filter(|c| c.ts >= since).copied().collect())
+                .unwrap_or_default();
+            if recorded.len() >= 2 { Ok((recorded, "Recorded")) } else { other }
+        }
+    }
+}
+
+async fn handle_chart(bot: &Bot, state: &Arc<AppState>, message: &Message, data: &str) -> ResponseResult<()> {
+    let chat_id = message.chat.id;
+    let parts: Vec<&str> = data.splitn(5, ':').collect();
+    if parts.len() != 5 { return Ok(()); }
+    let (kind, symbol) = (parts[1], parts[4]);
+    let range = chart::ChartRange::parse(parts[2]).unwrap_or(chart::ChartRange::D7);
+    let style = chart::ChartStyle::parse(parts[3]).unwrap_or(chart::ChartStyle::Candle);
+
+    let rendered = match load_chart_candles(state, kind, symbol, range).await {
+        Ok((candles, source)) => chart::render_png(&candles, style).map(|png| (png, chart_caption(kind, symbol, range, style, &candles, source))),
+        Err(e) => Err(e),
+    };
+    let (png, caption) = match rendered {
+        Ok(r) => r,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+
+    let photo = InputFile::memory(png).file_name("chart.png");
+    let keyboard = make_chart_menu(kind, symbol, range, style);
+    if message.photo().is_some() {
+        let media = InputMedia::Photo(InputMediaPhoto::new(photo).caption(caption).parse_mode(ParseMode::Html));
+        bot.edit_message_media(chat_id, message.id, media).reply_markup(keyboard).await?;
+    } else {
+        bot.send_photo(chat_id, photo).caption(caption).parse_mode(ParseMode::Html).reply_markup(keyboard).await?;
+    }
+    Ok(())
+}
+
+async fn handle_compare_chart(bot: &Bot, message: &Message, data: &str) -> ResponseResult<()> {
+    let chat_id = message.chat.id;
+    let parts: Vec<&str> = data.splitn(3, ':').collect();
+    let [_, range, keys] = parts.as_slice() else { return Ok(()); };
+    let range = chart::ChartRange::parse(range).unwrap_or(chart::ChartRange::D30);
+
+    let handles: Vec<_> = keys.split(',').filter_map(|k| k.split_once('.')).map(|(kind, ticker)| {
+        let (kind, ticker) = (kind.to_string(), ticker.to_string());
+        let task_ticker = ticker.clone();
+        let handle = tokio::spawn(async move {
+            match kind.as_str() {
+                "s" => api::fetch_stock_history(&task_ticker, range).await,
+                _ => api::fetch_crypto_history(&task_ticker, range).await,
+            }
+        });
+        (ticker, handle)
+    }).collect();
+
+    let mut series = Vec::new();
+    let mut legend = Vec::new();
+    for (i, (ticker, handle)) in handles.into_iter().enumerate() {
+        let candles = handle.await.ok().and_then(|r| r.ok()).unwrap_or_default();
+        let change = chart::relative_change(&candles).map(|c| format!("{:+.2}%", c)).unwrap_or_else(|| "N/A".to_string());
+        legend.push(format!("{} <b>{}</b>: <code>{}</code>", chart::SERIES_LEGEND[i % chart::SERIES_LEGEND.len()], ticker, change));
+        series.push(candles);
+    }
+
+    let png = match chart::render_comparison_png(&series) {
+        Ok(p) => p,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    let caption = format!(
+        "⚖️ <b>RELATIVE PERFORMANCE</b> • <code>{}</code>\n========================\n{}\n========================\n<i>💡 Dinormalisasi ke 100 di awal periode</i>",
+        range.code(), legend.join("\n")
+    );
+    let photo = InputFile::memory(png).file_name("compare.png");
+    let keyboard = make_compare_chart_menu(keys, range);
+    if message.photo().is_some() {
+        let media = InputMedia::Photo(InputMediaPhoto::new(photo).caption(caption).parse_mode(ParseMode::Html));
+        bot.edit_message_media(chat_id, message.id, media).reply_markup(keyboard).await?;
+    } else {
+        bot.send_photo(chat_id, photo).caption(caption).parse_mode(ParseMode::Html).reply_markup(keyboard).await?;
+    }
+    Ok(())
+}
+
+fn chart_caption(kind: &str, symbol: &str, range: chart::ChartRange, style: chart::ChartStyle, candles: &[chart::Candle], source: &str) -> String {
+    let fmt = |p: f64| -> String {
+        match kind {
+            "s" => format!("Rp {}", format_angka(p)),
+            _ if p < 1.0 => format!("${:.8}", p),
+            _ => format!("${:.2}", p),
+        }
+    };
+    let open = candles.first().map(|c| c.open).unwrap_or(0.0);
+    let close = candles.last().map(|c| c.close).unwrap_or(0.0);
+    let high = candles.iter().fold(f64::MIN, |m, c| m.max(c.high));
+    let low = candles.iter().fold(f64::MAX, |m, c| m.min(c.low));
+    let volume: f64 = candles.iter().map(|c| c.volume).sum();
+    let change = if open > 0.0 { (close - open) / open * 100.0 } else { 0.0 };
+    let emoji = if change >= 0.0 { "📈" } else { "📉" };
+    format!(
+        "{} <b>{}</b> • <code>{}</code> • {}\n========================\n• Open: <code>{}</code>\n• High: <code>{}</code>\n• Low: <code>{}</code>\n• Close: <code>{}</code>\n• Change: <code>{:+.2}%</code>\n• Volume: <code>{}</code>\n========================\n<i>💡 Source: {}</i>",
+        emoji, if kind == "t" { symbol.to_string() } else { symbol.to_uppercase() }, range.code(), style.label(),
+        fmt(open), fmt(high), fmt(low), fmt(close), change, api::format_number(volume), source
+    )
+}
+
+async fn get_coin_rule(state: &Arc<AppState>, symbol: &str) -> crypto_syariah::CoinRule {
+    state.coin_rules.lock().await.get(&symbol.to_uppercase()).cloned().unwrap_or_else(crypto_syariah::CoinRule::unreviewed)
+}
+
+async fn check_coin_filter(state: &Arc<AppState>, chat_id: ChatId, symbol: &str) -> Result<(), String> {
+    let filter = state.coin_filters.lock().await.get(&chat_id).copied().unwrap_or_default();
+    let rule = get_coin_rule(state, symbol).await;
+    if filter.allows(&rule) {
+        Ok(())
+    } else {
+        Err(format!("{} diblokir filter syariah ({}): {} - {}", symbol, filter.label(), rule.status.label(), rule.reason.label()))
+    }
+}
+
+async fn execute_buy(state: &Arc<AppState>, chat_id: ChatId, symbol: &str) -> Result<String, String> {
+    check_coin_filter(state, chat_id, symbol).await?;
+    let price = get_price(state, symbol).await;
+    let amount = 1000.0;
+    let qty = amount / price;
+    let mut portfolios = state.portfolios.lock().await;
+    let p = portfolios.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new(), since: Utc::now().timestamp() });
+    if p.balance < amount { return Err("Insufficient balance!".to_string()); }
+    p.balance -= amount;
+    let h = p.holdings.entry(symbol.to_string()).or_insert(Holding { symbol: symbol.to_string(), quantity: 0.0, avg_price: 0.0, since: Utc::now().timestamp() });
+    let total_cost = h.quantity * h.avg_price + amount;
+    h.quantity += qty;
+    h.avg_price = total_cost / h.quantity;
+    Ok(format!("✅ <b>BUY ORDER</b>\n\n{}: {:.6} @ ${:.2}\nTotal: ${:.2}\nBalance: ${:.2}", symbol, qty, price, amount, p.balance))
+}
+
+async fn execute_sell(state: &Arc<AppState>, chat_id: ChatId, symbol: &str) -> Result<String, String> {
+    let price = get_price(state, symbol).await;
+    let mut portfolios = state.portfolios.lock().await;
+    let p = portfolios.entry(chat_id).or_insert_with(|| UserPortfolio { balance: 10000.0, holdings: HashMap::new(), since: Utc::now().timestamp() });
+    let h = p.holdings.remove(symbol).ok_or("No holdings for this crypto")?;
+    let value = h.quantity * price;
+    let pnl = value - (h.quantity * h.avg_price);
+    p.balance += value;
+    let emoji = if pnl >= 0.0 { "📈" } else { "📉" };
+    Ok(format!("✅ <b>SELL ORDER</b>\n\n{}: {:.6} @ ${:.2}\nValue: ${:.2}\n{} P&L: ${:.2}\nBalance: ${:.2}", symbol, h.quantity, price, value, emoji, pnl.abs(), p.balance))
+}
+
+fn make_main_menu() -> InlineKeyboardMarkup { 
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("🪙 CRYPTO", "menu_crypto"), InlineKeyboardButton::callback("🕌 SAHAM", "menu_sharia")],
+        vec![InlineKeyboardButton::callback("⚡️ SOLANA", "menu_solana"), InlineKeyboardButton::callback("🌡 SENTIMENT", "menu_sentiment_info")],
+        vec![InlineKeyboardButton::callback("🎮 SIMULATOR", "menu_sim_main"), InlineKeyboardButton::callback("🔎 SCREENER", "menu_screener")],
+        vec![InlineKeyboardButton::callback("🚀 REAL BUY", "menu_solana_real"), InlineKeyboardButton::callback("⭐ WATCHLIST", "menu_watchlist")],
+        vec![InlineKeyboardButton::callback("👛 WALLET", "menu_wallet"), InlineKeyboardButton::callback("👁 WATCH", "menu_wallet_watch")],
+        vec![InlineKeyboardButton::callback("🕋 ZAKAT", "menu_zakat"), InlineKeyboardButton::callback("📡 FEED", "menu_feed")],
+        vec![InlineKeyboardButton::callback("📒 LEDGER", "menu_ledger")],
+        vec![InlineKeyboardButton::callback("❓ HELP", "menu_help"), InlineKeyboardButton::callback("🔄 REFRESH", "back_to_main")]
+    ])
+}
+
+fn make_sim_menu() -> InlineKeyboardMarkup { 
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("📈 BUY $1000", "menu_buy"), InlineKeyboardButton::callback("📉 SELL ALL", "menu_sell")],
+        vec![InlineKeyboardButton::callback("💼 PORTFOLIO", "menu_portfolio"), InlineKeyboardButton::callback("🕌 FILTER", "menu_coin_filter")],
+        vec![InlineKeyboardButton::callback("⚖️ REBALANCE", "rebal:sim"), InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
+    ])
+}
+
+fn make_admin_menu() -> InlineKeyboardMarkup { 
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("📊 DASHBOARD", "admin_dashboard"), InlineKeyboardButton::callback("👥 USERS", "admin_users")],
+        vec![InlineKeyboardButton::callback("💰 WALLET", "admin_wallet"), InlineKeyboardButton::callback("📈 ANALYTICS", "admin_analytics")],
+        vec![InlineKeyboardButton::callback("🎁 GIFT PREMIUM", "admin_gift_premium"), InlineKeyboardButton::callback("📩 DM USER", "admin_dm")],
+        vec![InlineKeyboardButton::callback("🚫 BAN", "admin_ban"), InlineKeyboardButton::callback("✅ UNBAN", "admin_unban")],
+        vec![InlineKeyboardButton::callback("📢 BROADCAST", "admin_broadcast"), InlineKeyboardButton::callback("🔧 SYSTEM", "admin_system")],
+        vec![InlineKeyboardButton::callback("➕ ADD GROUP", "admin_add_group"), InlineKeyboardButton::callback("➖ REM GROUP", "admin_rem_group")],
+        vec![InlineKeyboardButton::callback("🕌 CRYPTO RULES", "admin_coin_rules")],
+        vec![InlineKeyboardButton::callback("❌ CLOSE", "back_to_main")]
+    ])
+}
+
+fn make_sentiment_menu() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("🎭 Fear & Greed", "sentiment_fng"), InlineKeyboardButton::callback("📈 Market Pulse", "sentiment_pulse")],
+        vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
+    ])
 }
 
 fn make_watchlist_menu() -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(vec![
         vec![InlineKeyboardButton::callback("➕ ADD", "watchlist_add"), InlineKeyboardButton::callback("📊 CHECK ALL", "watchlist_check")],
-        vec![InlineKeyboardButton::callback("🗑 CLEAR", "watchlist_clear"), InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
+        vec![InlineKeyboardButton::callback("🗑 CLEAR", "watchlist_clear"), InlineKeyboardButton::callback("🕌 FILTER", "menu_coin_filter")],
+        vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]
+    ])
+}
+
+fn make_real_buy_amount_menu(mint: &str) -> InlineKeyboardMarkup {
+    let limits = swap::SwapLimits::from_env();
+    let buttons: Vec<InlineKeyboardButton> = swap::AMOUNT_PRESETS.iter()
+        .filter(|a| **a <= limits.max_trade_sol)
+        .map(|a| InlineKeyboardButton::callback(format!("◎ {}", a), format!("rbq:{}:{}", a, mint)))
+        .collect();
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = buttons.chunks(2).map(|c| c.to_vec()).collect();
+    rows.push(vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_real_buy_confirm_menu(mint: &str, sol: f64) -> InlineKeyboardMarkup {
+    let mut second = vec![InlineKeyboardButton::callback("🧪 DRY RUN", "rb_dry")];
+    let requote = format!("rbq:{:.4}:{}", sol, mint);
+    if requote.len() <= CALLBACK_DATA_MAX {
+        second.push(InlineKeyboardButton::callback("🔄 QUOTE BARU", requote));
+    }
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("✅ CONFIRM", "rb_confirm"), InlineKeyboardButton::callback("❌ BATAL", "rb_cancel")],
+        second,
+    ])
+}
+
+fn dex_prompt(chain: api::Chain) -> String {
+    format!(
+        "⚡️ <b>DEX TOKEN</b> • {}\n\nMasukkan ticker atau CA:\n<i>Contoh: BONK, atau PEPE eth untuk jaringan lain</i>",
+        chain.label()
+    )
+}
+
+fn make_chain_menu(selected: api::Chain) -> InlineKeyboardMarkup {
+    let chains: Vec<InlineKeyboardButton> = api::Chain::ALL.iter().map(|c| {
+        let label = if *c == selected { format!("• {} •", c.label()) } else { c.label().to_string() };
+        InlineKeyboardButton::callback(label, format!("dex_chain:{}", c.code()))
+    }).collect();
+    InlineKeyboardMarkup::new(vec![chains])
+}
+
+fn make_token_choice_menu(chain: api::Chain, tokens: &[api::DexPairData]) -> InlineKeyboardMarkup {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = tokens.iter().take(5).enumerate().map(|(i, t)| {
+        let liquidity = t.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+        vec![InlineKeyboardButton::callback(
+            format!("{}. {} (${})", i + 1, t.base_token.symbol, api::format_compact(liquidity)),
+            format!("dex:{}:{}", chain.code(), t.base_token.address),
+        )]
+    }).collect();
+    rows.extend(make_back_menu().inline_keyboard);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_holders_menu(mint: &str) -> InlineKeyboardMarkup {
+    let mut rows = vec![vec![InlineKeyboardButton::callback("🥧 PIE CHART", format!("holders_pie:{}", mint))]];
+    rows.extend(make_back_menu().inline_keyboard);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_dex_token_menu(pair: &api::DexPairData) -> InlineKeyboardMarkup {
+    let mut rows = make_result_menu("t", &api::dex_chart_symbol(pair)).inline_keyboard;
+    if api::Chain::parse(&pair.chain_id) == Some(api::Chain::Solana) {
+        rows[0].push(InlineKeyboardButton::callback("👥 Holders", format!("holders:{}", pair.base_token.address)));
+    }
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_result_menu(kind: &str, symbol: &str) -> InlineKeyboardMarkup {
+    // The chart menu later swaps in the longest range code; past the limit Telegram rejects the whole message.
+    let fits = !symbol.is_empty() && !symbol.contains(char::is_whitespace) && format!("chart:{}:30D:k:{}", kind, symbol).len() <= CALLBACK_DATA_MAX;
+    let mut rows = Vec::new();
+    if fits {
+        rows.push(vec![InlineKeyboardButton::callback("📈 Chart", format!("chart:{}:7D:k:{}", kind, symbol))]);
+    }
+    rows.extend(make_back_menu().inline_keyboard);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_stock_menu(ticker: &str, standard: syariah::Standard) -> InlineKeyboardMarkup {
+    let standards = syariah::Standard::ALL.iter().map(|m| {
+        let label = if *m == standard { format!("• {} •", m.name()) } else { m.name().to_string() };
+        InlineKeyboardButton::callback(label, format!("syariah:{}:{}", m.code(), ticker))
+    }).collect();
+    let mut rows = vec![standards, vec![InlineKeyboardButton::callback("⭐ WATCH", format!("wl_add:{}", ticker))]];
+    rows.extend(make_result_menu("s", ticker).inline_keyboard);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_screener_menu(filter: &screener::ScreenerFilter, page: usize, pages: usize) -> InlineKeyboardMarkup {
+    let nav = vec![
+        InlineKeyboardButton::callback("⬅️", format!("scr_page:{}", page.saturating_sub(1))),
+        InlineKeyboardButton::callback(format!("{}/{}", page + 1, pages), format!("scr_page:{}", page)),
+        InlineKeyboardButton::callback("➡️", format!("scr_page:{}", (page + 1).min(pages - 1))),
+    ];
+    let mut rows = vec![nav];
+    for chunk in screener::SortField::ALL.chunks(3) {
+        rows.push(chunk.iter().map(|f| {
+            let label = if *f == filter.sort { format!("{} {}", f.label(), if filter.desc { "↓" } else { "↑" }) } else { f.label().to_string() };
+            InlineKeyboardButton::callback(label, format!("scr_sort:{}", f.code()))
+        }).collect());
+    }
+    rows.push(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+async fn make_ledger_menu(state: &Arc<AppState>, chat_id: ChatId) -> InlineKeyboardMarkup {
+    let method = state.ledgers.lock().await.get(&chat_id).map(|b| b.method).unwrap_or_default();
+    let toggle = match method {
+        ledger::CostMethod::Fifo => ("⚙️ PAKAI AVERAGE", "ledger_method:avg"),
+        ledger::CostMethod::Average => ("⚙️ PAKAI FIFO", "ledger_method:fifo"),
+    };
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("➕ CATAT", "ledger_add"), InlineKeyboardButton::callback("🧾 RIWAYAT", "ledger_trades")],
+        vec![InlineKeyboardButton::callback("🔄 REFRESH", "menu_ledger"), InlineKeyboardButton::callback(toggle.0, toggle.1)],
+        vec![InlineKeyboardButton::callback("📥 IMPOR CSV", "ledger_import"), InlineKeyboardButton::callback("📤 EKSPOR", "menu_export")],
+        vec![InlineKeyboardButton::callback("🕋 ZAKAT", "menu_zakat"), InlineKeyboardButton::callback("💼 PAJAK", format!("tax:{}", Utc::now().year()))],
+        vec![InlineKeyboardButton::callback("⚖️ REBALANCE", "rebal:ledger"), InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]
+    ])
+}
+
+fn make_rebalance_menu(executable: bool) -> InlineKeyboardMarkup {
+    let mut rows = Vec::new();
+    if executable {
+        rows.push(vec![InlineKeyboardButton::callback("✅ EKSEKUSI DI SIMULATOR", "rebal_exec")]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback("🎮 SIMULATOR", "rebal:sim"), InlineKeyboardButton::callback("📒 LEDGER", "rebal:ledger")]);
+    rows.push(vec![InlineKeyboardButton::callback("✏️ UBAH TARGET", "rebal_set"), InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_tax_menu(year: i32) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback(format!("◀️ {}", year - 1), format!("tax:{}", year - 1)),
+            InlineKeyboardButton::callback(format!("{} ▶️", year + 1), format!("tax:{}", year + 1)),
+        ],
+        vec![
+            InlineKeyboardButton::callback("📄 CSV", format!("tax_exp:{}:csv", year)),
+            InlineKeyboardButton::callback("🖨 TEKS (.txt)", format!("tax_exp:{}:txt", year)),
+        ],
+        vec![InlineKeyboardButton::callback("📒 LEDGER", "menu_ledger"), InlineKeyboardButton::callback("🏠 HOME", "back_to_main")],
+    ])
+}
+
+fn make_export_menu() -> InlineKeyboardMarkup {
+    let rows = [("🧾 TRADES", "trades"), ("📒 LEDGER", "ledger"), ("🎮 SIMULATOR", "sim"), ("⭐ WATCHLIST", "watchlist")].iter()
+        .map(|(label, what)| vec![
+            InlineKeyboardButton::callback(format!("{} CSV", label), format!("exp:{}:csv", what)),
+            InlineKeyboardButton::callback(format!("{} JSON", label), format!("exp:{}:json", what)),
+        ])
+        .chain(std::iter::once(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]))
+        .collect::<Vec<_>>();
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_import_menu(has_trades: bool) -> InlineKeyboardMarkup {
+    let mut rows = Vec::new();
+    if has_trades {
+        rows.push(vec![InlineKeyboardButton::callback("✅ IMPOR", "imp_confirm"), InlineKeyboardButton::callback("❌ BATAL", "imp_cancel")]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_zakat_menu(sim: bool) -> InlineKeyboardMarkup {
+    let (current, other) = if sim {
+        ("zakat_sim", InlineKeyboardButton::callback("📒 PORTOFOLIO RIIL", "menu_zakat"))
+    } else {
+        ("menu_zakat", InlineKeyboardButton::callback("🎮 SIMULATOR", "zakat_sim"))
+    };
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("🔄 HITUNG ULANG", current), other],
+        vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]
+    ])
+}
+
+fn make_purification_menu(can_save: bool) -> InlineKeyboardMarkup {
+    let mut rows = Vec::new();
+    if can_save { rows.push(vec![InlineKeyboardButton::callback("✅ CATAT", "tzk_save")]); }
+    rows.push(vec![InlineKeyboardButton::callback("📊 RINGKASAN TAHUNAN", "tzk_summary")]);
+    rows.push(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_compare_menu(chart_keys: Option<&str>) -> InlineKeyboardMarkup {
+    let mut rows = Vec::new();
+    if let Some(keys) = chart_keys {
+        rows.push(vec![InlineKeyboardButton::callback("📈 RELATIVE CHART", format!("cmp:30D:{}", keys))]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_compare_chart_menu(keys: &str, range: chart::ChartRange) -> InlineKeyboardMarkup {
+    let ranges = chart::ChartRange::ALL.iter().map(|r| {
+        let label = if *r == range { format!("• {} •", r.code()) } else { r.code().to_string() };
+        InlineKeyboardButton::callback(label, format!("cmp:{}:{}", r.code(), keys))
+    }).collect();
+    InlineKeyboardMarkup::new(vec![ranges, vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]])
+}
+
+fn make_chart_menu(kind: &str, symbol: &str, range: chart::ChartRange, style: chart::ChartStyle) -> InlineKeyboardMarkup {
+    let ranges = chart::ChartRange::ALL.iter().map(|r| {
+        let label = if *r == range { format!("• {} •", r.code()) } else { r.code().to_string() };
+        InlineKeyboardButton::callback(label, format!("chart:{}:{}:{}:{}", kind, r.code(), style.code(), symbol))
+    }).collect();
+    let toggle = match style {
+        chart::ChartStyle::Candle => ("📉 LINE", chart::ChartStyle::Line),
+        chart::ChartStyle::Line => ("🕯 CANDLE", chart::ChartStyle::Candle),
+    };
+    InlineKeyboardMarkup::new(vec![
+        ranges,
+        vec![
+            InlineKeyboardButton::callback(toggle.0, format!("chart:{}:{}:{}:{}", kind, range.code(), toggle.1.code(), symbol)),
+            InlineKeyboardButton::callback("🔄 REFRESH", format!("chart:{}:{}:{}:{}", kind, range.code(), style.code(), symbol))
+        ],
+        vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]
+    ])
+}
+
+async fn make_feed_menu(state: &AppState, chat_id: ChatId) -> InlineKeyboardMarkup {
+    let subscribed = state.feed_subscribers.lock().await.contains(&chat_id);
+    InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback(if subscribed { "🔕 BERHENTI" } else { "🔔 LANGGANAN" }, "feed_toggle"),
+            InlineKeyboardButton::callback("👀 PREVIEW", "feed_preview"),
+        ],
+        vec![InlineKeyboardButton::callback("🔙 BACK", "back_to_main")],
+    ])
+}
+
+fn make_wallet_menu(address: &str) -> InlineKeyboardMarkup {
+    let mut rows = Vec::new();
+    if let Ok(pk) = solana::parse_pubkey(address) {
+        rows.push(vec![
+            InlineKeyboardButton::callback("👁 WATCH WALLET", format!("ww_add:{}", pk)),
+            InlineKeyboardButton::callback("🥩 STAKING", format!("stake:{}", pk)),
+        ]);
+    }
+    rows.extend(make_back_menu().inline_keyboard);
+    InlineKeyboardMarkup::new(rows)
+}
+
+async fn make_wallet_watch_menu(state: &AppState, chat_id: ChatId) -> InlineKeyboardMarkup {
+    let list = state.wallet_watches.lock().await.get(&chat_id).cloned().unwrap_or_default();
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = list.iter()
+        .map(|a| vec![InlineKeyboardButton::callback(format!("🗑 {}", solana::short_address(a)), format!("ww_del:{}", a))])
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback("👛 CEK WALLET", "menu_wallet"), InlineKeyboardButton::callback("🔙 BACK", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_solstatus_menu() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("🔄 REFRESH", "solstatus_refresh"), InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]
     ])
 }
 
@@ -725,6 +2730,22 @@ fn make_help_menu() -> InlineKeyboardMarkup {
     ])
 }
 
+fn make_coin_filter_menu(current: crypto_syariah::CoinFilter) -> InlineKeyboardMarkup {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = crypto_syariah::CoinFilter::ALL.iter().map(|f| {
+        let label = if *f == current { format!("• {} •", f.label()) } else { f.label().to_string() };
+        vec![InlineKeyboardButton::callback(label, format!("cf:{}", f.code()))]
+    }).collect();
+    rows.push(vec![InlineKeyboardButton::callback("🏠 HOME", "back_to_main")]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+fn make_coin_rules_menu() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("➕ SET RULE", "admin_coin_set"), InlineKeyboardButton::callback("➖ REMOVE RULE", "admin_coin_del")],
+        vec![InlineKeyboardButton::callback("🔄 REFRESH", "admin_coin_rules"), InlineKeyboardButton::callback("🔙 PANEL", "back_to_panel")]
+    ])
+}
+
 fn make_admin_action_menu() -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(vec![
         vec![
@@ -733,4 +2754,4 @@ fn make_admin_action_menu() -> InlineKeyboardMarkup {
         ],
         vec![InlineKeyboardButton::callback("❌ CLOSE", "back_to_main")]
     ])
-}
\ No newline at end of file
+}
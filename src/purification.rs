@@ -0,0 +1,171 @@
+use crate::api::format_number;
+use crate::ledger::parse_number;
+use std::collections::{BTreeMap, HashMap};
+
+// ==========================================
+// DIVIDEND PURIFICATION (TAZKIYAH)
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct PurificationEntry {
+    pub ticker: String,
+    pub shares: f64,
+    pub dividend_per_share: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PurificationRecord {
+    pub year: i32,
+    pub ticker: String,
+    pub shares: f64,
+    pub dividend_per_share: f64,
+    pub non_halal: f64,
+}
+
+impl PurificationRecord {
+    pub fn dividend(&self) -> f64 {
+        self.shares * self.dividend_per_share
+    }
+
+    pub fn amount(&self) -> f64 {
+        self.dividend() * self.non_halal / 100.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PurificationOptions {
+    pub whole: bool,
+    pub sim: bool,
+    pub year: Option<i32>,
+}
+
+// A first line made only of options ("semua", "sim", "tahun=2024") is consumed; anything else is an entry.
+pub fn parse_options(text: &str, current_year: i32) -> Result<(PurificationOptions, &str), String> {
+    let text = text.trim_start();
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let mut opts = PurificationOptions::default();
+    for token in first.split_whitespace() {
+        match token.to_lowercase().as_str() {
+            "semua" | "all" => opts.whole = true,
+            "sim" => opts.sim = true,
+            t if t.starts_with("tahun=") => {
+                let year: i32 = t["tahun=".len()..].parse().map_err(|_| format!("Tahun tidak valid: {}", token))?;
+                if !(2000..=current_year).contains(&year) {
+                    return Err(format!("Tahun harus antara 2000 dan {}", current_year));
+                }
+                opts.year = Some(year);
+            }
+            _ => return Ok((PurificationOptions::default(), text)),
+        }
+    }
+    Ok((opts, rest))
+}
+
+// One entry per line: TICKER SHARES DIVIDEND_PER_SHARE, e.g. "BBRI 1000 150".
+// "TICKER DIVIDEND_PER_SHARE" takes the share count from `ledger_shares` (open positions in /ledger).
+pub fn parse_entries(text: &str, ledger_shares: &HashMap<String, f64>) -> Result<Vec<PurificationEntry>, String> {
+    let mut entries = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (ticker, shares, dps) = match parts.as_slice() {
+            [ticker, shares, dps] => (ticker.to_uppercase(), parse_number(shares)?, *dps),
+            [ticker, dps] => {
+                let ticker = ticker.to_uppercase();
+                let shares = ledger_shares.get(&ticker).copied()
+                    .ok_or_else(|| format!("{} tidak ada di /ledger, tulis jumlah lembar", ticker))?;
+                (ticker, shares, *dps)
+            }
+            _ => return Err(format!("Format salah: {}", line)),
+        };
+        entries.push(PurificationEntry {
+            ticker,
+            shares,
+            dividend_per_share: parse_number(dps)?,
+        });
+    }
+    if entries.is_empty() {
+        return Err("Belum ada data dividen".to_string());
+    }
+    Ok(entries)
+}
+
+// Whole-portfolio mode: every held stock is covered, holdings without a dividend line are reported as skipped.
+pub fn parse_whole_portfolio(text: &str, holdings: &HashMap<String, f64>) -> Result<(Vec<PurificationEntry>, Vec<String>), String> {
+    if holdings.is_empty() {
+        return Err("Belum ada saham di portofolio".to_string());
+    }
+    let mut entries = parse_entries(text, holdings)?;
+    entries.retain(|e| e.dividend_per_share > 0.0);
+    let mut skipped: Vec<String> = holdings.keys()
+        .filter(|t| !entries.iter().any(|e| &e.ticker == *t))
+        .cloned()
+        .collect();
+    skipped.sort();
+    Ok((entries, skipped))
+}
+
+pub fn format_template(holdings: &HashMap<String, f64>, sim: bool) -> String {
+    let mut tickers: Vec<_> = holdings.iter().collect();
+    tickers.sort_by(|a, b| a.0.cmp(b.0));
+    let header = if sim { "semua sim" } else { "semua" };
+    let mut out = format!(
+        "🧼 <b>TAZKIYAH PORTOFOLIO</b>\n========================\n📂 Sumber: {}\n\n",
+        if sim { "🎮 Simulator" } else { "📒 Ledger" }
+    );
+    for (ticker, shares) in &tickers {
+        out.push_str(&format!("• <b>{}</b>: {} lbr\n", ticker, format_number(**shares)));
+    }
+    out.push_str(&format!(
+        "\nKirim dividen per lembar tiap saham:\n<code>/tazkiyah {}\n{}</code>\n========================\n<i>💡 Saham tanpa baris dividen dilewati</i>",
+        header,
+        tickers.iter().map(|(t, _)| format!("{} 0", t)).collect::<Vec<_>>().join("\n")
+    ));
+    out
+}
+
+pub fn format_result(records: &[PurificationRecord], errors: &[String], skipped: &[String]) -> String {
+    let mut out = "🧼 <b>TAZKIYAH DIVIDEN</b>\n========================\n".to_string();
+    for r in records {
+        out.push_str(&format!(
+            "• <b>{}</b>: {} lbr × Rp {} = <code>Rp {}</code>\n   Non-Halal {:.2}% → 🤲 <code>Rp {}</code>\n",
+            r.ticker, format_number(r.shares), r.dividend_per_share, format_number(r.dividend()),
+            r.non_halal, format_number(r.amount())
+        ));
+    }
+    for e in errors {
+        out.push_str(&format!("❌ {}\n", e));
+    }
+    if !skipped.is_empty() {
+        out.push_str(&format!("⏭️ Dilewati (tanpa dividen): {}\n", skipped.join(", ")));
+    }
+    let dividend: f64 = records.iter().map(|r| r.dividend()).sum();
+    let amount: f64 = records.iter().map(|r| r.amount()).sum();
+    out.push_str(&format!(
+        "\n💰 Total Dividen: <code>Rp {}</code>\n🤲 Wajib Disucikan: <code>Rp {}</code>\n💧 Dividen Bersih: <code>Rp {}</code>\n========================\n<i>💡 Salurkan dana tazkiyah untuk kepentingan sosial, bukan sebagai zakat</i>",
+        format_number(dividend), format_number(amount), format_number(dividend - amount)
+    ));
+    out
+}
+
+pub fn format_summary(records: &[PurificationRecord]) -> String {
+    if records.is_empty() {
+        return "📊 <b>RINGKASAN TAZKIYAH</b>\n\n📭 Belum ada catatan".to_string();
+    }
+    let mut by_year: BTreeMap<i32, BTreeMap<String, (f64, f64)>> = BTreeMap::new();
+    for r in records {
+        let e = by_year.entry(r.year).or_default().entry(r.ticker.clone()).or_insert((0.0, 0.0));
+        e.0 += r.dividend();
+        e.1 += r.amount();
+    }
+
+    let mut out = "📊 <b>RINGKASAN TAZKIYAH</b>\n========================\n".to_string();
+    for (year, tickers) in by_year.iter().rev() {
+        let total: f64 = tickers.values().map(|t| t.1).sum();
+        out.push_str(&format!("\n📅 <b>{}</b> • Total: <code>Rp {}</code>\n", year, format_number(total)));
+        for (ticker, (dividend, amount)) in tickers {
+            out.push_str(&format!("• {}: dividen <code>Rp {}</code> → 🤲 <code>Rp {}</code>\n", ticker, format_number(*dividend), format_number(*amount)));
+        }
+    }
+    out.push_str("========================");
+    out
+}
@@ -0,0 +1,344 @@
+use crate::api::{escape_html, format_number};
+use crate::ledger::{AssetClass, Side};
+use crate::solana::short_address;
+
+const IDX_LOT: f64 = 100.0;
+const FRACTION_STEP: f64 = 0.000001;
+const MAX_TARGETS: usize = 10;
+
+// ==========================================
+// TARGET ALLOCATION
+// ==========================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bucket { Class(AssetClass), Asset(String) }
+
+impl Bucket {
+    fn parse_word(word: &str) -> Option<Self> {
+        match word.to_lowercase().as_str() {
+            "idx" | "stocks" | "saham" | "stock" => Some(Bucket::Class(AssetClass::Stock)),
+            "kas" | "cash" | "usd" | "idr" => Some(Bucket::Class(AssetClass::Cash)),
+            other => AssetClass::parse(other).map(Bucket::Class),
+        }
+    }
+
+    fn parse(name: &str) -> Self {
+        let words: Vec<&str> = name.split_whitespace().collect();
+        words.iter().find_map(|w| Self::parse_word(w)).unwrap_or_else(|| {
+            let symbol = words.first().copied().unwrap_or_default();
+            // Solana mints are case-sensitive.
+            Bucket::Asset(if symbol.len() >= 32 { symbol.to_string() } else { symbol.to_uppercase() })
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Bucket::Class(AssetClass::Cash) => "💵 Kas".to_string(),
+            Bucket::Class(c) => c.label().to_string(),
+            Bucket::Asset(s) if s.len() >= 32 => format!("⚡️ {}", short_address(s)),
+            Bucket::Asset(s) => format!("🎯 {}", escape_html(s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub bucket: Bucket,
+    pub weight: f64,
+}
+
+// "60% saham / 30% BTC / 10% cash": one weight per segment separated by "/", ";" or new lines.
+// Commas are decimal separators here ("12,5% BTC / 87,5% kas").
+pub fn parse_targets(text: &str) -> Result<Vec<Target>, String> {
+    let mut targets: Vec<Target> = Vec::new();
+    for segment in text.split(['/', ';', '\n']).map(str::trim).filter(|s| !s.is_empty()) {
+        let mut weight = None;
+        let mut words = Vec::new();
+        for token in segment.split_whitespace() {
+            match token.trim_end_matches('%').replace(',', ".").parse::<f64>() {
+                Ok(w) if weight.is_none() => weight = Some(w),
+                _ => words.push(token),
+            }
+        }
+        let weight = weight.ok_or_else(|| format!("Persentase tidak ditemukan: {}", segment))?;
+        if words.is_empty() {
+            return Err(format!("Aset tidak ditemukan: {}", segment));
+        }
+        if weight <= 0.0 || weight > 100.0 {
+            return Err(format!("Persentase harus 0-100: {}", segment));
+        }
+        let bucket = Bucket::parse(&words.join(" "));
+        if targets.iter().any(|t| t.bucket == bucket) {
+            return Err(format!("Target ganda: {}", segment));
+        }
+        targets.push(Target { bucket, weight });
+    }
+    if targets.is_empty() {
+        return Err("Target kosong".to_string());
+    }
+    if targets.len() > MAX_TARGETS {
+        return Err(format!("Maksimal {} target", MAX_TARGETS));
+    }
+    let sum: f64 = targets.iter().map(|t| t.weight).sum();
+    if (sum - 100.0).abs() > 0.5 {
+        return Err(format!("Total target harus 100% (sekarang {:.1}%)", sum));
+    }
+    Ok(targets)
+}
+
+pub fn format_targets(targets: &[Target]) -> String {
+    let mut out = "🎯 <b>TARGET ALOKASI</b>\n".to_string();
+    for (i, t) in targets.iter().enumerate() {
+        let branch = if i + 1 == targets.len() { "└" } else { "├" };
+        out.push_str(&format!("{} {}: <code>{:.1}%</code>\n", branch, t.bucket.label(), t.weight));
+    }
+    out
+}
+
+// ==========================================
+// PORTFOLIO SNAPSHOT
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Venue { Simulator, Ledger }
+
+impl Venue {
+    pub fn label(&self) -> &'static str {
+        match self { Venue::Simulator => "🎮 SIMULATOR", Venue::Ledger => "📒 LEDGER RIIL" }
+    }
+
+    pub fn money(&self, n: f64) -> String {
+        match self {
+            Venue::Simulator => format!("${:.2}", n),
+            Venue::Ledger => format!("Rp {}", format_number(n)),
+        }
+    }
+
+    // Orders below this value are skipped; override with REBALANCE_MIN_ORDER_USD / REBALANCE_MIN_ORDER_IDR.
+    pub fn min_order(&self) -> f64 {
+        let read = |key: &str, default: f64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        match self {
+            Venue::Simulator => read("REBALANCE_MIN_ORDER_USD", 10.0),
+            Venue::Ledger => read("REBALANCE_MIN_ORDER_IDR", 100_000.0),
+        }
+    }
+
+    fn supports(&self, class: AssetClass) -> bool {
+        match self {
+            Venue::Simulator => matches!(class, AssetClass::Crypto | AssetClass::Cash),
+            Venue::Ledger => true,
+        }
+    }
+}
+
+fn lot_size(class: AssetClass) -> f64 {
+    if class == AssetClass::Stock { IDX_LOT } else { FRACTION_STEP }
+}
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub class: AssetClass,
+    pub symbol: String,
+    pub quantity: f64,
+    pub price: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Holdings {
+    pub venue: Venue,
+    pub cash: f64,
+    pub assets: Vec<Asset>,
+}
+
+// ==========================================
+// PLAN
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub class: AssetClass,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+impl Order {
+    pub fn value(&self) -> f64 {
+        self.quantity * self.price
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub label: String,
+    pub target: f64,
+    pub current: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub venue: Venue,
+    pub total: f64,
+    pub drifts: Vec<Drift>,
+    pub orders: Vec<Order>,
+    pub cash_after: f64,
+    pub notes: Vec<String>,
+}
+
+fn round_to_lot(quantity: f64, class: AssetClass) -> f64 {
+    let lot = lot_size(class);
+    (quantity / lot + 1e-9).floor() * lot
+}
+
+pub fn build_plan(holdings: &Holdings, targets: &[Target]) -> Plan {
+    let venue = holdings.venue;
+    let min_order = venue.min_order();
+    let mut notes = Vec::new();
+    let priced: Vec<(&Asset, f64)> = holdings.assets.iter()
+        .filter_map(|a| match a.price {
+            Some(p) if p > 0.0 => Some((a, p)),
+            _ => {
+                if a.quantity > 0.0 {
+                    notes.push(format!("Harga {} tidak tersedia, tidak ikut dihitung", escape_html(&a.symbol)));
+                }
+                None
+            }
+        })
+        .collect();
+    let total = holdings.cash + priced.iter().map(|(a, p)| a.quantity * p).sum::<f64>();
+
+    // Each asset belongs to its own target first, then to its class target; anything else is sold.
+    let bucket_of = |a: &Asset| -> Option<usize> {
+        targets.iter().position(|t| t.bucket == Bucket::Asset(a.symbol.clone()))
+            .or_else(|| targets.iter().position(|t| t.bucket == Bucket::Class(a.class)))
+    };
+
+    let mut drifts = Vec::new();
+    let mut wanted: Vec<(&Asset, f64, f64)> = Vec::new();
+    for (i, t) in targets.iter().enumerate() {
+        let members: Vec<(&Asset, f64)> = priced.iter().filter(|(a, _)| bucket_of(a) == Some(i)).copied().collect();
+        let current = if t.bucket == Bucket::Class(AssetClass::Cash) {
+            holdings.cash
+        } else {
+            members.iter().map(|(a, p)| a.quantity * p).sum()
+        };
+        drifts.push(Drift { label: t.bucket.label(), target: t.weight, current: if total > 0.0 { current / total * 100.0 } else { 0.0 } });
+
+        let class = match &t.bucket {
+            Bucket::Class(c) => Some(*c),
+            Bucket::Asset(_) => members.first().map(|(a, _)| a.class),
+        };
+        if class == Some(AssetClass::Cash) {
+            continue;
+        }
+        if class.is_some_and(|c| !venue.supports(c)) {
+            notes.push(format!("{} tidak tersedia di simulator, porsinya tetap kas", t.bucket.label()));
+            continue;
+        }
+        let diff = t.weight / 100.0 * total - current;
+        if members.is_empty() {
+            if diff >= min_order {
+                notes.push(format!("{} belum dimiliki: beli senilai {} (pilih aset lalu catat manual)", t.bucket.label(), venue.money(diff)));
+            }
+            continue;
+        }
+        // Class targets spread the difference over current holdings pro rata.
+        let count = members.len() as f64;
+        for (a, p) in members {
+            let share = if current > 0.0 { a.quantity * p / current } else { 1.0 / count };
+            wanted.push((a, p, diff * share));
+        }
+    }
+    for (a, p) in priced.iter().filter(|(a, _)| bucket_of(a).is_none() && a.quantity > 0.0) {
+        wanted.push((a, *p, -a.quantity * p));
+    }
+
+    let mut sells = Vec::new();
+    let mut buys = Vec::new();
+    for (a, price, diff) in wanted {
+        if diff.abs() < min_order {
+            continue;
+        }
+        let side = if diff > 0.0 { Side::Buy } else { Side::Sell };
+        let full_exit = side == Side::Sell && -diff >= a.quantity * price - 1e-9;
+        let quantity = if full_exit { a.quantity } else { round_to_lot(diff.abs() / price, a.class).min(if side == Side::Sell { a.quantity } else { f64::MAX }) };
+        if quantity <= 0.0 || quantity * price < min_order {
+            continue;
+        }
+        let order = Order { class: a.class, symbol: a.symbol.clone(), side, quantity, price };
+        if side == Side::Sell { sells.push(order) } else { buys.push(order) }
+    }
+
+    // Buys are funded by cash plus sell proceeds; scale down (per lot) if they do not fit.
+    let available = holdings.cash + sells.iter().map(Order::value).sum::<f64>();
+    let wanted_buys: f64 = buys.iter().map(Order::value).sum();
+    if wanted_buys > available && wanted_buys > 0.0 {
+        let scale = available.max(0.0) / wanted_buys;
+        for o in &mut buys {
+            o.quantity = round_to_lot(o.quantity * scale, o.class);
+        }
+        buys.retain(|o| o.quantity > 0.0 && o.value() >= min_order);
+    }
+
+    let cash_after = available - buys.iter().map(Order::value).sum::<f64>();
+    let mut orders = sells;
+    orders.extend(buys);
+    Plan { venue, total, drifts, orders, cash_after, notes }
+}
+
+fn format_quantity(class: AssetClass, quantity: f64) -> String {
+    if class == AssetClass::Stock {
+        format!("{} lbr ({} lot)", quantity, quantity / IDX_LOT)
+    } else {
+        format!("{:.6}", quantity)
+    }
+}
+
+pub fn format_plan(plan: &Plan) -> String {
+    let venue = plan.venue;
+    let mut out = format!(
+        "⚖️ <b>REBALANCE - {}</b>\n━━━━━━━━━━━━━━━━━━━━━━━\n💼 Total: <code>{}</code>\n\n🎯 <b>TARGET vs AKTUAL</b>\n",
+        venue.label(), venue.money(plan.total)
+    );
+    for (i, d) in plan.drifts.iter().enumerate() {
+        let drift = d.current - d.target;
+        let status = match drift.abs() {
+            x if x < 1.0 => "🟢",
+            x if x < 5.0 => "🟡",
+            _ => "🔴",
+        };
+        out.push_str(&format!(
+            "{} {} {}: <code>{:.1}%</code> → target <code>{:.1}%</code> (drift <code>{:+.1}%</code>)\n",
+            if i + 1 == plan.drifts.len() { "└" } else { "├" }, status, d.label, d.current, d.target, drift
+        ));
+    }
+
+    out.push_str("\n📝 <b>ORDER DISARANKAN</b>\n");
+    if plan.orders.is_empty() {
+        out.push_str("└ <i>Portofolio sudah sesuai target</i>\n");
+    }
+    for (i, o) in plan.orders.iter().enumerate() {
+        let symbol = if o.class == AssetClass::Token { short_address(&o.symbol) } else { o.symbol.clone() };
+        out.push_str(&format!(
+            "{} {} {} <b>{}</b> <code>{}</code> @ <code>{}</code> = <code>{}</code>\n",
+            if i + 1 == plan.orders.len() { "└" } else { "├" },
+            if o.side == Side::Buy { "🟢 BELI" } else { "🔴 JUAL" }, o.class.emoji(), escape_html(&symbol),
+            format_quantity(o.class, o.quantity), venue.money(o.price), venue.money(o.value())
+        ));
+    }
+    out.push_str(&format!("\n💵 Kas setelah rebalance: <code>{}</code>\n", venue.money(plan.cash_after)));
+
+    if !plan.notes.is_empty() {
+        out.push_str("\n⚠️ <b>CATATAN</b>\n");
+        for n in &plan.notes {
+            out.push_str(&format!("• {}\n", n));
+        }
+    }
+    out.push_str(&format!(
+        "━━━━━━━━━━━━━━━━━━━━━━━\n<i>💡 Saham dibulatkan per lot ({} lembar) • Order di bawah {} dilewati{}</i>",
+        IDX_LOT, venue.money(venue.min_order()),
+        if venue == Venue::Ledger { " • Eksekusi di broker lalu catat di /ledger" } else { "" }
+    ));
+    out
+}
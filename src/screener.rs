@@ -0,0 +1,175 @@
+use crate::api::{escape_html, SyariahApiResponse};
+use crate::syariah::indicator_value;
+use std::cmp::Ordering;
+
+pub const PAGE_SIZE: usize = 10;
+
+// ==========================================
+// FILTER
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortField { Code, Sector, Industry, MarketCap, Debt, NonHalal }
+
+impl SortField {
+    pub const ALL: [SortField; 6] = [SortField::Code, SortField::Sector, SortField::Industry, SortField::MarketCap, SortField::Debt, SortField::NonHalal];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            SortField::Code => "kode",
+            SortField::Sector => "sektor",
+            SortField::Industry => "industri",
+            SortField::MarketCap => "cap",
+            SortField::Debt => "hutang",
+            SortField::NonHalal => "nonhalal",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.code() == s)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortField::Code => "🔤 Kode",
+            SortField::Sector => "🏭 Sektor",
+            SortField::Industry => "🏗 Industri",
+            SortField::MarketCap => "💰 Cap",
+            SortField::Debt => "🏦 Hutang",
+            SortField::NonHalal => "🚫 Non-Halal",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenerFilter {
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub min_cap: Option<f64>,
+    pub max_cap: Option<f64>,
+    pub max_debt: Option<f64>,
+    pub max_non_halal: Option<f64>,
+    pub sort: SortField,
+    pub desc: bool,
+}
+
+impl Default for ScreenerFilter {
+    fn default() -> Self {
+        Self { sector: None, industry: None, min_cap: None, max_cap: None, max_debt: None, max_non_halal: None, sort: SortField::MarketCap, desc: true }
+    }
+}
+
+// Syntax: sektor=Keuangan industri=Bank cap=10-500 hutang=30 nonhalal=5 sort=cap order=desc
+// Market cap bounds are in trillion rupiah, underscores stand in for spaces.
+pub fn parse_filter(args: &str) -> Result<ScreenerFilter, String> {
+    let mut filter = ScreenerFilter::default();
+    for token in args.split_whitespace() {
+        let (key, value) = token.split_once('=').ok_or_else(|| format!("Format salah: {}", token))?;
+        let value = value.replace('_', " ");
+        let number = |v: &str| v.trim().parse::<f64>().map_err(|_| format!("Angka tidak valid: {}", v));
+        match key.to_lowercase().as_str() {
+            "sektor" => filter.sector = Some(value),
+            "industri" => filter.industry = Some(value),
+            "cap" => {
+                let (lo, hi) = value.split_once('-').unwrap_or((value.as_str(), ""));
+                if !lo.is_empty() { filter.min_cap = Some(number(lo)?); }
+                if !hi.is_empty() { filter.max_cap = Some(number(hi)?); }
+            }
+            "hutang" => filter.max_debt = Some(number(&value)?),
+            "nonhalal" => filter.max_non_halal = Some(number(&value)?),
+            "sort" => filter.sort = SortField::parse(&value.to_lowercase()).ok_or_else(|| format!("Sort tidak dikenal: {}", value))?,
+            "order" => filter.desc = value.eq_ignore_ascii_case("desc"),
+            _ => return Err(format!("Filter tidak dikenal: {}", key)),
+        }
+    }
+    Ok(filter)
+}
+
+fn contains(field: &Option<String>, needle: &Option<String>) -> bool {
+    match needle {
+        Some(n) => field.as_ref().map(|f| f.to_lowercase().contains(&n.to_lowercase())).unwrap_or(false),
+        None => true,
+    }
+}
+
+fn cap_trillion(s: &SyariahApiResponse) -> Option<f64> {
+    s.market_cap.map(|c| c / 1_000_000_000_000.0)
+}
+
+fn debt(s: &SyariahApiResponse) -> Option<f64> {
+    s.syariah_indicator.as_ref().and_then(|i| indicator_value(&i.hutang_bunga))
+}
+
+fn non_halal(s: &SyariahApiResponse) -> Option<f64> {
+    s.syariah_indicator.as_ref().and_then(|i| indicator_value(&i.non_halal))
+}
+
+fn within(value: Option<f64>, min: Option<f64>, max: Option<f64>) -> bool {
+    match value {
+        Some(v) => min.is_none_or(|m| v >= m) && max.is_none_or(|m| v <= m),
+        None => min.is_none() && max.is_none(),
+    }
+}
+
+pub fn apply(universe: &[SyariahApiResponse], filter: &ScreenerFilter) -> Vec<SyariahApiResponse> {
+    let mut rows: Vec<SyariahApiResponse> = universe.iter()
+        .filter(|s| s.issi.unwrap_or(false))
+        .filter(|s| contains(&s.sector, &filter.sector) && contains(&s.industry, &filter.industry))
+        .filter(|s| within(cap_trillion(s), filter.min_cap, filter.max_cap))
+        .filter(|s| within(debt(s), None, filter.max_debt))
+        .filter(|s| within(non_halal(s), None, filter.max_non_halal))
+        .cloned()
+        .collect();
+
+    let num = |a: Option<f64>, b: Option<f64>| a.unwrap_or(f64::MIN).partial_cmp(&b.unwrap_or(f64::MIN)).unwrap_or(Ordering::Equal);
+    rows.sort_by(|a, b| {
+        let ord = match filter.sort {
+            SortField::Code => a.code.cmp(&b.code),
+            SortField::Sector => a.sector.cmp(&b.sector),
+            SortField::Industry => a.industry.cmp(&b.industry),
+            SortField::MarketCap => num(a.market_cap, b.market_cap),
+            SortField::Debt => num(debt(a), debt(b)),
+            SortField::NonHalal => num(non_halal(a), non_halal(b)),
+        };
+        if filter.desc { ord.reverse() } else { ord }
+    });
+    rows
+}
+
+pub fn page_count(total: usize) -> usize {
+    total.div_ceil(PAGE_SIZE).max(1)
+}
+
+pub fn format_page(rows: &[SyariahApiResponse], filter: &ScreenerFilter, page: usize) -> String {
+    let pct = |v: Option<f64>| v.map(|x| format!("{:.2}%", x)).unwrap_or_else(|| "N/A".to_string());
+    let mut active = Vec::new();
+    if let Some(s) = &filter.sector { active.push(format!("sektor={}", escape_html(s))); }
+    if let Some(i) = &filter.industry { active.push(format!("industri={}", escape_html(i))); }
+    if filter.min_cap.is_some() || filter.max_cap.is_some() {
+        active.push(format!("cap={}-{}T", filter.min_cap.map(|v| v.to_string()).unwrap_or_default(), filter.max_cap.map(|v| v.to_string()).unwrap_or_default()));
+    }
+    if let Some(d) = filter.max_debt { active.push(format!("hutang≤{}%", d)); }
+    if let Some(n) = filter.max_non_halal { active.push(format!("nonhalal≤{}%", n)); }
+
+    let mut out = format!(
+        "🔎 <b>ISSI SCREENER</b>\n========================\n• Filter: <code>{}</code>\n• Sort: <code>{} {}</code>\n• Hasil: <code>{} saham</code> • Hal {}/{}\n\n",
+        if active.is_empty() { "semua".to_string() } else { active.join(" ") },
+        filter.sort.code(), if filter.desc { "↓" } else { "↑" },
+        rows.len(), page + 1, page_count(rows.len())
+    );
+
+    if rows.is_empty() {
+        out.push_str("📭 <i>Tidak ada saham yang cocok</i>\n");
+    }
+    for (i, s) in rows.iter().enumerate().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+        out.push_str(&format!(
+            "{}. <b>{}</b> - {}\n   {} • Rp {:.2}T • H {} • NH {}\n",
+            i + 1, escape_html(&s.code), escape_html(&s.name),
+            escape_html(s.sector.as_deref().unwrap_or("N/A")),
+            cap_trillion(s).unwrap_or(0.0),
+            pct(debt(s)), pct(non_halal(s))
+        ));
+    }
+    out.push_str("\n========================\n<i>💡 /screener sektor=Energi cap=10-500 hutang=30 nonhalal=5 sort=cap order=desc</i>");
+    out
+}
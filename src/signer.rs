@@ -0,0 +1,210 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use async_trait::async_trait;
+use base64::Engine;
+use hmac::Hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+
+const KEYSTORE_ITERATIONS: u32 = 600_000;
+
+// ==========================================
+// SIGNER ABSTRACTION
+// ==========================================
+
+// Implementations only ever expose the public key. A remote signer (KMS, HSM, signing service)
+// only has to implement `sign_message`; transaction assembly stays in `sign_transaction`.
+#[async_trait]
+pub trait TxSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+    fn source(&self) -> &'static str;
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, String>;
+}
+
+pub struct LocalSigner {
+    keypair: Keypair,
+    source: &'static str,
+}
+
+impl std::fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner").field("pubkey", &self.keypair.pubkey()).field("source", &self.source).finish()
+    }
+}
+
+#[async_trait]
+impl TxSigner for LocalSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn source(&self) -> &'static str {
+        self.source
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, String> {
+        Ok(self.keypair.sign_message(message))
+    }
+}
+
+pub async fn sign_transaction(signer: &dyn TxSigner, mut tx: VersionedTransaction) -> Result<VersionedTransaction, String> {
+    let required = tx.message.header().num_required_signatures as usize;
+    let index = tx.message.static_account_keys().iter()
+        .take(required)
+        .position(|k| *k == signer.pubkey())
+        .ok_or_else(|| "Wallet bot bukan penandatangan transaksi ini".to_string())?;
+    let signature = signer.sign_message(&tx.message.serialize()).await?;
+    tx.signatures.resize(required, Signature::default());
+    tx.signatures[index] = signature;
+    Ok(tx)
+}
+
+// ==========================================
+// LOADING
+// ==========================================
+
+fn keypair_from_bytes(mut bytes: Vec<u8>, what: &str) -> Result<Keypair, String> {
+    let keypair = Keypair::from_bytes(&bytes).map_err(|_| format!("{} tidak valid", what));
+    bytes.fill(0);
+    keypair
+}
+
+fn load_keypair_file(path: &str) -> Result<Keypair, String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("Gagal membaca {}: {}", path, e.kind()))?;
+    let bytes: Vec<u8> = serde_json::from_str(&raw).map_err(|_| format!("{} bukan keypair JSON Solana CLI", path))?;
+    keypair_from_bytes(bytes, path)
+}
+
+// Lookup order: encrypted keystore, Solana CLI keypair file, then the legacy plain SOLANA_PRIVATE_KEY.
+pub fn load_signer() -> Result<Arc<dyn TxSigner>, String> {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+
+    let (keypair, source) = if let Some(path) = env("SOLANA_KEYSTORE") {
+        let passphrase = env("SOLANA_KEYSTORE_PASSPHRASE").ok_or_else(|| "SOLANA_KEYSTORE_PASSPHRASE belum diset".to_string())?;
+        (decrypt_keystore(&path, &passphrase)?, "Encrypted keystore")
+    } else if let Some(path) = env("SOLANA_KEYPAIR_PATH") {
+        (load_keypair_file(&path)?, "Solana CLI keypair")
+    } else if let Some(secret) = env("SOLANA_PRIVATE_KEY") {
+        log::warn!("SOLANA_PRIVATE_KEY is stored in plain text; move it to SOLANA_KEYSTORE");
+        let bytes = bs58::decode(secret.trim()).into_vec().map_err(|_| "SOLANA_PRIVATE_KEY tidak valid".to_string())?;
+        (keypair_from_bytes(bytes, "SOLANA_PRIVATE_KEY")?, "Plain env (SOLANA_PRIVATE_KEY)")
+    } else {
+        return Err("Signer belum dikonfigurasi".to_string());
+    };
+    Ok(Arc::new(LocalSigner { keypair, source }))
+}
+
+// ==========================================
+// ENCRYPTED KEYSTORE
+// ==========================================
+
+#[derive(Serialize, Deserialize)]
+struct Keystore {
+    version: u8,
+    pubkey: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn decrypt_keystore(path: &str, passphrase: &str) -> Result<Keypair, String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("Gagal membaca {}: {}", path, e.kind()))?;
+    let store: Keystore = serde_json::from_str(&raw).map_err(|_| format!("{} bukan keystore yang valid", path))?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let salt = b64.decode(&store.salt).map_err(|_| "Keystore rusak".to_string())?;
+    let nonce = b64.decode(&store.nonce).map_err(|_| "Keystore rusak".to_string())?;
+    let ciphertext = b64.decode(&store.ciphertext).map_err(|_| "Keystore rusak".to_string())?;
+    let nonce: [u8; 12] = nonce.try_into().map_err(|_| "Keystore rusak".to_string())?;
+
+    let mut key = derive_key(passphrase, &salt, store.iterations);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| "Keystore rusak".to_string());
+    key.fill(0);
+    let plain = cipher?.decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+        .map_err(|_| "Passphrase salah atau keystore rusak".to_string())?;
+    let keypair = keypair_from_bytes(plain, "Keystore")?;
+    if keypair.pubkey().to_string() != store.pubkey {
+        return Err("Pubkey keystore tidak cocok".to_string());
+    }
+    Ok(keypair)
+}
+
+// Used by `lubix_bot encrypt-keystore <keypair.json> <keystore.json>`.
+pub fn encrypt_keystore(keypair_path: &str, out_path: &str, passphrase: &str) -> Result<Pubkey, String> {
+    write_keystore(&load_keypair_file(keypair_path)?, out_path, passphrase, KEYSTORE_ITERATIONS)
+}
+
+fn write_keystore(keypair: &Keypair, out_path: &str, passphrase: &str, iterations: u32) -> Result<Pubkey, String> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut key = derive_key(passphrase, &salt, iterations);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| "Gagal membuat cipher".to_string());
+    key.fill(0);
+    let mut secret = keypair.to_bytes();
+    let ciphertext = cipher?.encrypt(&Nonce::from(nonce), secret.as_ref()).map_err(|_| "Gagal enkripsi".to_string());
+    secret.fill(0);
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let store = Keystore {
+        version: 1,
+        pubkey: keypair.pubkey().to_string(),
+        iterations,
+        salt: b64.encode(salt),
+        nonce: b64.encode(nonce),
+        ciphertext: b64.encode(ciphertext?),
+    };
+    let json = serde_json::to_string_pretty(&store).map_err(|e| e.to_string())?;
+    std::fs::write(out_path, json).map_err(|e| format!("Gagal menulis {}: {}", out_path, e.kind()))?;
+    Ok(keypair.pubkey())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // PBKDF2 at the production count takes seconds; the roundtrip does not depend on it.
+    const TEST_ITERATIONS: u32 = 1_000;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("lubix_{}_{}.json", name, std::process::id())).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn keystore_roundtrip_restores_the_keypair() {
+        let keypair = Keypair::new();
+        let path = temp_path("keystore_roundtrip");
+        let pubkey = write_keystore(&keypair, &path, "correct horse", TEST_ITERATIONS).unwrap();
+        assert_eq!(pubkey, keypair.pubkey());
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains(&bs58::encode(keypair.to_bytes()).into_string()));
+
+        let restored = decrypt_keystore(&path, "correct horse").unwrap();
+        assert_eq!(restored.pubkey(), keypair.pubkey());
+        assert_eq!(restored.to_bytes(), keypair.to_bytes());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let path = temp_path("keystore_wrong_passphrase");
+        write_keystore(&Keypair::new(), &path, "correct horse", TEST_ITERATIONS).unwrap();
+        let err = decrypt_keystore(&path, "battery staple").unwrap_err();
+        assert_eq!(err, "Passphrase salah atau keystore rusak");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
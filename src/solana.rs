@@ -0,0 +1,512 @@
+use crate::api::{escape_html, format_compact, DexPairData, USD_IDR};
+use chrono::{TimeZone, Utc};
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::epoch_info::EpochInfo;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::state::Mint;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+// ==========================================
+// RPC HELPERS
+// ==========================================
+
+pub fn rpc_url() -> String {
+    std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string())
+}
+
+pub fn rpc_client() -> RpcClient {
+    RpcClient::new_with_commitment(rpc_url(), CommitmentConfig::confirmed())
+}
+
+pub fn parse_pubkey(s: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(s.trim()).map_err(|_| format!("Address tidak valid: {}", s.trim()))
+}
+
+pub fn short_address(s: &str) -> String {
+    if s.len() > 12 { format!("{}…{}", &s[..4], &s[s.len() - 4..]) } else { s.to_string() }
+}
+
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM).unwrap_or_default();
+    Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &program).0
+}
+
+// ==========================================
+// WALLET BALANCES
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct TokenBalance {
+    pub mint: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletOverview {
+    pub address: String,
+    pub lamports: u64,
+    pub tokens: Vec<TokenBalance>,
+}
+
+impl WalletOverview {
+    pub fn sol(&self) -> f64 {
+        lamports_to_sol(self.lamports)
+    }
+}
+
+pub async fn fetch_token_balances(client: &RpcClient, owner: &Pubkey) -> Result<Vec<TokenBalance>, String> {
+    let mut tokens = Vec::new();
+    for program in [TOKEN_PROGRAM, TOKEN_2022_PROGRAM] {
+        let program_id = Pubkey::from_str(program).map_err(|e| e.to_string())?;
+        let accounts = match client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(program_id)).await {
+            Ok(a) => a,
+            Err(_) if program == TOKEN_2022_PROGRAM => continue,
+            Err(e) => return Err(format!("RPC error: {}", e)),
+        };
+        for keyed in accounts {
+            let UiAccountData::Json(parsed) = &keyed.account.data else { continue };
+            let info = &parsed.parsed["info"];
+            let amount = &info["tokenAmount"];
+            tokens.push(TokenBalance {
+                mint: info["mint"].as_str().unwrap_or_default().to_string(),
+                amount: amount["uiAmountString"].as_str().and_then(|v| v.parse().ok()).unwrap_or(0.0),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+pub async fn fetch_wallet(address: &str) -> Result<WalletOverview, String> {
+    let owner = parse_pubkey(address)?;
+    let client = rpc_client();
+    let lamports = client.get_balance(&owner).await.map_err(|e| format!("RPC error: {}", e))?;
+    let tokens = fetch_token_balances(&client, &owner).await?;
+    Ok(WalletOverview { address: owner.to_string(), lamports, tokens })
+}
+
+pub fn format_wallet(wallet: &WalletOverview, prices: &HashMap<String, (String, f64)>) -> String {
+    let sol_price = prices.get(WSOL_MINT).map(|p| p.1).unwrap_or(0.0);
+    let sol_value = wallet.sol() * sol_price;
+
+    let mut rows: Vec<(String, f64, Option<f64>)> = wallet.tokens.iter()
+        .filter(|t| t.amount > 0.0)
+        .map(|t| {
+            let (symbol, price) = prices.get(&t.mint).cloned().map(|(s, p)| (s, Some(p))).unwrap_or_else(|| (short_address(&t.mint), None));
+            (symbol, t.amount, price.map(|p| p * t.amount))
+        })
+        .collect();
+    rows.sort_by(|a, b| b.2.unwrap_or(0.0).partial_cmp(&a.2.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal));
+
+    let token_total: f64 = rows.iter().filter_map(|r| r.2).sum();
+    let empty = wallet.tokens.len() - rows.len();
+    let total = sol_value + token_total;
+
+    let mut out = format!(
+        "👛 <b>SOLANA WALLET</b>\n========================\n• Address: <code>{}</code>\n\n◎ <b>SOL BALANCE:</b>\n• <code>{:.4} SOL</code> ≈ <code>${:.2}</code>\n\n🪙 <b>TOKENS ({}):</b>\n",
+        wallet.address, wallet.sol(), sol_value, rows.len()
+    );
+    if rows.is_empty() {
+        out.push_str("<i>Tidak ada token</i>\n");
+    }
+    for (symbol, amount, value) in rows.iter().take(25) {
+        out.push_str(&format!(
+            "• <b>{}</b>: <code>{}</code> ≈ <code>{}</code>\n",
+            escape_html(symbol), format_compact(*amount), value.map(|v| format!("${:.2}", v)).unwrap_or_else(|| "N/A".to_string())
+        ));
+    }
+    if rows.len() > 25 {
+        out.push_str(&format!("<i>… dan {} token lainnya</i>\n", rows.len() - 25));
+    }
+    if empty > 0 {
+        out.push_str(&format!("<i>{} akun token kosong disembunyikan</i>\n", empty));
+    }
+    out.push_str(&format!(
+        "\n💰 <b>TOTAL VALUE:</b>\n• USD: <code>${:.2}</code>\n• IDR: <code>Rp {}</code>\n========================\n<i>💡 Saldo via Solana RPC • Harga via DexScreener</i>",
+        total, format_compact(total * USD_IDR)
+    ));
+    out
+}
+
+// ==========================================
+// HOT WALLET
+// ==========================================
+
+pub const SWAP_COMPUTE_UNITS: u64 = 200_000;
+const BASE_FEE_LAMPORTS: u64 = 5_000;
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+// Micro-lamports per compute unit over the last ~150 slots.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityFee {
+    pub median: u64,
+    pub p75: u64,
+}
+
+impl PriorityFee {
+    pub fn lamports(&self, micro_per_cu: u64) -> u64 {
+        micro_per_cu * SWAP_COMPUTE_UNITS / 1_000_000
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HotWallet {
+    pub overview: WalletOverview,
+    pub signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    pub priority_fee: Option<PriorityFee>,
+    pub fee_reserve: u64,
+}
+
+async fn fetch_recent_fees(client: &RpcClient) -> Result<Vec<u64>, String> {
+    let mut fees: Vec<u64> = client.get_recent_prioritization_fees(&[]).await
+        .map_err(|e| format!("RPC error: {}", e))?
+        .into_iter()
+        .map(|f| f.prioritization_fee)
+        .collect();
+    fees.sort_unstable();
+    Ok(fees)
+}
+
+// `fees` must be sorted ascending.
+fn percentiles(fees: &[u64]) -> PriorityFee {
+    if fees.is_empty() {
+        return PriorityFee::default();
+    }
+    PriorityFee { median: fees[fees.len() / 2], p75: fees[fees.len() * 3 / 4] }
+}
+
+pub async fn fetch_priority_fee(client: &RpcClient) -> Result<PriorityFee, String> {
+    Ok(percentiles(&fetch_recent_fees(client).await?))
+}
+
+// Minimum SOL for one more swap: base fee, a p75 priority fee and rent for a new token account.
+pub async fn fee_reserve(client: &RpcClient, priority_fee: Option<PriorityFee>) -> u64 {
+    let rent = client.get_minimum_balance_for_rent_exemption(TOKEN_ACCOUNT_LEN).await.unwrap_or(2_039_280);
+    let floor = std::env::var("WALLET_MIN_SOL").ok().and_then(|v| v.parse::<f64>().ok()).map(sol_to_lamports).unwrap_or(0);
+    (BASE_FEE_LAMPORTS + rent + priority_fee.map(|p| p.lamports(p.p75)).unwrap_or(0)).max(floor)
+}
+
+pub async fn fetch_hot_wallet(owner: &Pubkey) -> Result<HotWallet, String> {
+    let client = rpc_client();
+    let lamports = client.get_balance(owner).await.map_err(|e| format!("RPC error: {}", e))?;
+    let tokens = fetch_token_balances(&client, owner).await?;
+    let config = GetConfirmedSignaturesForAddress2Config { limit: Some(5), ..Default::default() };
+    let signatures = client.get_signatures_for_address_with_config(owner, config).await.unwrap_or_default();
+    let priority_fee = fetch_priority_fee(&client).await.ok();
+    let fee_reserve = fee_reserve(&client, priority_fee).await;
+    Ok(HotWallet {
+        overview: WalletOverview { address: owner.to_string(), lamports, tokens },
+        signatures,
+        priority_fee,
+        fee_reserve,
+    })
+}
+
+pub fn format_hot_wallet(wallet: &HotWallet, signer_source: &str, dry_run: bool, prices: &HashMap<String, (String, f64)>) -> String {
+    let overview = &wallet.overview;
+    let sol_price = prices.get(WSOL_MINT).map(|p| p.1).unwrap_or(0.0);
+    let mut out = format!(
+        "💰 <b>BOT WALLET</b>\n━━━━━━━━━━━━━━━━━━━━━━━\n├ 🔑 Pubkey: <code>{}</code>\n├ 🗄 Signer: <code>{}</code>\n├ 🧪 Dry-run: <code>{}</code>\n└ ◎ SOL: <code>{:.4}</code> ≈ <code>${:.2}</code>\n",
+        overview.address, signer_source, if dry_run { "ON" } else { "OFF" }, overview.sol(), overview.sol() * sol_price
+    );
+    if overview.lamports < wallet.fee_reserve {
+        out.push_str(&format!(
+            "\n🚨 <b>SALDO SOL RENDAH!</b>\nMinimal <code>{:.4} SOL</code> dibutuhkan untuk fee & rent akun token.\n",
+            lamports_to_sol(wallet.fee_reserve)
+        ));
+    }
+
+    let mut tokens: Vec<(String, f64, Option<f64>)> = overview.tokens.iter()
+        .filter(|t| t.amount > 0.0)
+        .map(|t| match prices.get(&t.mint) {
+            Some((symbol, price)) => (symbol.clone(), t.amount, Some(price * t.amount)),
+            None => (short_address(&t.mint), t.amount, None),
+        })
+        .collect();
+    tokens.sort_by(|a, b| b.2.unwrap_or(0.0).partial_cmp(&a.2.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal));
+    out.push_str(&format!("\n🪙 <b>TOKENS ({})</b>\n", tokens.len()));
+    if tokens.is_empty() {
+        out.push_str("└ <i>Tidak ada token</i>\n");
+    }
+    for (i, (symbol, amount, value)) in tokens.iter().take(10).enumerate() {
+        out.push_str(&format!(
+            "{} <b>{}</b>: <code>{}</code> ≈ <code>{}</code>\n",
+            if i + 1 == tokens.len().min(10) { "└" } else { "├" },
+            escape_html(symbol), format_compact(*amount), value.map(|v| format!("${:.2}", v)).unwrap_or_else(|| "N/A".to_string())
+        ));
+    }
+
+    out.push_str("\n📜 <b>RECENT SIGNATURES</b>\n");
+    if wallet.signatures.is_empty() {
+        out.push_str("└ <i>Belum ada transaksi</i>\n");
+    }
+    for (i, sig) in wallet.signatures.iter().enumerate() {
+        let status = match (&sig.err, &sig.confirmation_status) {
+            (Some(_), _) => "❌ failed".to_string(),
+            (None, Some(c)) => format!("✅ {:?}", c).to_lowercase(),
+            (None, None) => "✅".to_string(),
+        };
+        let time = sig.block_time.and_then(|t| Utc.timestamp_opt(t, 0).single())
+            .map(|t| t.format("%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{} <a href=\"https://solscan.io/tx/{}\">{}</a> • {} • <code>{}</code>\n",
+            if i + 1 == wallet.signatures.len() { "└" } else { "├" }, sig.signature, short_address(&sig.signature), status, time
+        ));
+    }
+
+    match wallet.priority_fee {
+        Some(fee) => out.push_str(&format!(
+            "\n⛽ <b>PRIORITY FEE</b> ({}K CU)\n├ Median: <code>{} µL/CU</code> ≈ <code>{:.6} SOL</code>\n└ P75: <code>{} µL/CU</code> ≈ <code>{:.6} SOL</code>\n",
+            SWAP_COMPUTE_UNITS / 1000, fee.median, lamports_to_sol(fee.lamports(fee.median)), fee.p75, lamports_to_sol(fee.lamports(fee.p75))
+        )),
+        None => out.push_str("\n⛽ <b>PRIORITY FEE</b>\n└ <i>Tidak tersedia</i>\n"),
+    }
+    out.push_str("━━━━━━━━━━━━━━━━━━━━━━━");
+    out
+}
+
+// ==========================================
+// NETWORK STATUS
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct NetworkStatus {
+    pub slot: u64,
+    pub block_height: u64,
+    pub tps: f64,
+    pub non_vote_tps: Option<f64>,
+    pub slot_time_ms: f64,
+    // Throughput covers the performance samples (one per minute); fees cover the ~150 slots the RPC keeps.
+    pub sample_minutes: f64,
+    pub epoch: EpochInfo,
+    pub priority_fee: PriorityFee,
+    pub fee_max: u64,
+    pub fee_nonzero_pct: f64,
+}
+
+impl NetworkStatus {
+    pub fn epoch_progress(&self) -> f64 {
+        if self.epoch.slots_in_epoch == 0 { return 0.0; }
+        self.epoch.slot_index as f64 / self.epoch.slots_in_epoch as f64 * 100.0
+    }
+
+    pub fn epoch_eta_hours(&self) -> f64 {
+        let remaining = self.epoch.slots_in_epoch.saturating_sub(self.epoch.slot_index);
+        remaining as f64 * self.slot_time_ms / 3_600_000.0
+    }
+
+    // Judged on the p75 fee (what a swap needs to land quickly) and on slot time drifting above the 400ms target.
+    pub fn congestion(&self) -> (&'static str, &'static str) {
+        match (self.priority_fee.p75, self.slot_time_ms) {
+            (fee, ms) if fee >= 500_000 || ms >= 600.0 => ("🔴", "PADAT - naikkan priority fee atau tunda transaksi"),
+            (fee, ms) if fee >= 50_000 || ms >= 480.0 => ("🟡", "RAMAI - gunakan priority fee P75"),
+            _ => ("🟢", "LANCAR"),
+        }
+    }
+}
+
+pub async fn fetch_network_status() -> Result<NetworkStatus, String> {
+    let client = rpc_client();
+    let (slot, block_height, epoch, samples, fees) = tokio::join!(
+        client.get_slot(),
+        client.get_block_height(),
+        client.get_epoch_info(),
+        client.get_recent_performance_samples(Some(30)),
+        fetch_recent_fees(&client)
+    );
+    let rpc_err = |e: solana_client::client_error::ClientError| format!("RPC error: {}", e);
+    let samples = samples.map_err(rpc_err)?;
+    let fees = fees.unwrap_or_default();
+
+    let secs: f64 = samples.iter().map(|s| s.sample_period_secs as f64).sum();
+    let txs: u64 = samples.iter().map(|s| s.num_transactions).sum();
+    let slots: u64 = samples.iter().map(|s| s.num_slots).sum();
+    // Older RPC nodes do not report non-vote counts.
+    let non_vote: Option<u64> = samples.iter().map(|s| s.num_non_vote_transactions).sum();
+    let per_sec = |n: u64| if secs > 0.0 { n as f64 / secs } else { 0.0 };
+
+    Ok(NetworkStatus {
+        slot: slot.map_err(rpc_err)?,
+        block_height: block_height.map_err(rpc_err)?,
+        tps: per_sec(txs),
+        non_vote_tps: non_vote.map(per_sec),
+        slot_time_ms: if slots > 0 { secs * 1000.0 / slots as f64 } else { 400.0 },
+        sample_minutes: secs / 60.0,
+        epoch: epoch.map_err(rpc_err)?,
+        priority_fee: percentiles(&fees),
+        fee_max: fees.last().copied().unwrap_or(0),
+        fee_nonzero_pct: if fees.is_empty() { 0.0 } else { fees.iter().filter(|f| **f > 0).count() as f64 / fees.len() as f64 * 100.0 },
+    })
+}
+
+pub fn format_network_status(status: &NetworkStatus, sol_price: Option<f64>) -> String {
+    let (emoji, label) = status.congestion();
+    let fee = status.priority_fee;
+    let bar_len = (status.epoch_progress() / 10.0).round() as usize;
+    let price = match sol_price {
+        Some(p) => format!("<code>${:.2}</code> • <code>Rp {}</code>", p, format_compact(p * USD_IDR)),
+        None => "<i>Tidak tersedia</i>".to_string(),
+    };
+    format!(
+        "🌐 <b>SOLANA NETWORK STATUS</b>\n========================\n{} <b>{}</b>\n\n⛓ <b>CHAIN:</b>\n• Slot: <code>{}</code>\n• Block height: <code>{}</code>\n• Slot time: <code>{:.0} ms</code>\n\n⚡️ <b>THROUGHPUT</b> (~{:.0} menit):\n• TPS: <code>{:.0}</code>\n• Non-vote TPS: <code>{}</code>\n\n⛽ <b>PRIORITY FEE</b> ({}K CU, ~150 slot):\n• Median: <code>{} µL/CU</code> ≈ <code>{:.6} SOL</code>\n• P75: <code>{} µL/CU</code> ≈ <code>{:.6} SOL</code>\n• Max: <code>{} µL/CU</code>\n• Slot dengan fee: <code>{:.0}%</code>\n\n🗓 <b>EPOCH {}:</b>\n• {}{} <code>{:.1}%</code>\n• Sisa: <code>~{:.1} jam</code>\n\n◎ <b>SOL:</b> {}\n========================\n<i>💡 Data dari RPC</i>",
+        emoji, label,
+        status.slot, status.block_height, status.slot_time_ms,
+        status.sample_minutes, status.tps, status.non_vote_tps.map(|t| format!("{:.0}", t)).unwrap_or_else(|| "N/A".to_string()),
+        SWAP_COMPUTE_UNITS / 1000,
+        fee.median, lamports_to_sol(fee.lamports(fee.median)), fee.p75, lamports_to_sol(fee.lamports(fee.p75)),
+        status.fee_max, status.fee_nonzero_pct,
+        status.epoch.epoch, "▰".repeat(bar_len.min(10)), "▱".repeat(10 - bar_len.min(10)), status.epoch_progress(),
+        status.epoch_eta_hours(), price
+    )
+}
+
+// ==========================================
+// TOKEN SAFETY
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct MintInfo {
+    pub program: &'static str,
+    pub program_id: Pubkey,
+    pub supply: u64,
+    pub decimals: u8,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+impl MintInfo {
+    pub fn ui_supply(&self) -> f64 {
+        self.supply as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SafetyReport {
+    pub mint: MintInfo,
+    pub top10_share: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    pub pair_age_hours: Option<f64>,
+}
+
+impl SafetyReport {
+    // Each finding carries the points it adds to the risk score (0 = aman, 100 = sangat berisiko).
+    pub fn findings(&self) -> Vec<(u32, String)> {
+        let mut out = Vec::new();
+        if self.mint.mint_authority.is_some() {
+            out.push((30, "Mint authority masih aktif, supply bisa ditambah".to_string()));
+        }
+        if self.mint.freeze_authority.is_some() {
+            out.push((20, "Freeze authority masih aktif, akun holder bisa dibekukan".to_string()));
+        }
+        match self.top10_share {
+            Some(s) if s > 80.0 => out.push((25, format!("Top 10 holder menguasai {:.1}% supply", s))),
+            Some(s) if s > 50.0 => out.push((15, format!("Top 10 holder menguasai {:.1}% supply", s))),
+            Some(s) if s > 30.0 => out.push((5, format!("Top 10 holder menguasai {:.1}% supply", s))),
+            Some(_) => {}
+            None => out.push((10, "Data holder tidak tersedia".to_string())),
+        }
+        match self.liquidity_usd {
+            Some(l) if l < 10_000.0 => out.push((20, format!("Likuiditas sangat rendah (${})", format_compact(l)))),
+            Some(l) if l < 50_000.0 => out.push((10, format!("Likuiditas rendah (${})", format_compact(l)))),
+            Some(_) => {}
+            None => out.push((10, "Pair likuiditas tidak ditemukan".to_string())),
+        }
+        match self.pair_age_hours {
+            Some(h) if h < 24.0 => out.push((15, format!("Pair baru berumur {:.0} jam", h))),
+            Some(h) if h < 24.0 * 7.0 => out.push((5, format!("Pair berumur {:.0} hari", h / 24.0))),
+            _ => {}
+        }
+        out
+    }
+
+    pub fn risk_score(&self) -> u32 {
+        self.findings().iter().map(|f| f.0).sum::<u32>().min(100)
+    }
+
+    pub fn risk_label(&self) -> &'static str {
+        match self.risk_score() {
+            0..=20 => "🟢 LOW",
+            21..=50 => "🟡 MEDIUM",
+            _ => "🔴 HIGH",
+        }
+    }
+}
+
+pub async fn fetch_mint_info(client: &RpcClient, mint: &Pubkey) -> Result<MintInfo, String> {
+    let account = client.get_account(mint).await.map_err(|e| format!("RPC error: {}", e))?;
+    let program = match account.owner.to_string().as_str() {
+        TOKEN_PROGRAM => "SPL Token",
+        TOKEN_2022_PROGRAM => "Token-2022",
+        _ => return Err("Address bukan SPL mint".to_string()),
+    };
+    // Token-2022 mints append extensions after the base layout, so only the first Mint::LEN bytes are decoded.
+    let data = account.data.get(..Mint::LEN).ok_or_else(|| "Data mint tidak valid".to_string())?;
+    let state = Mint::unpack_from_slice(data).map_err(|_| "Data mint tidak valid".to_string())?;
+    if !state.is_initialized {
+        return Err("Mint belum diinisialisasi".to_string());
+    }
+    Ok(MintInfo {
+        program,
+        program_id: account.owner,
+        supply: state.supply,
+        decimals: state.decimals,
+        mint_authority: state.mint_authority.into(),
+        freeze_authority: state.freeze_authority.into(),
+    })
+}
+
+pub async fn fetch_top10_share(client: &RpcClient, mint: &Pubkey, supply: u64) -> Result<f64, String> {
+    let largest = client.get_token_largest_accounts(mint).await.map_err(|e| format!("RPC error: {}", e))?;
+    let top: u128 = largest.iter().take(10).filter_map(|a| a.amount.amount.parse::<u128>().ok()).sum();
+    Ok(if supply > 0 { top as f64 / supply as f64 * 100.0 } else { 0.0 })
+}
+
+pub async fn check_token_safety(mint: &str, pair: Option<&DexPairData>) -> Result<SafetyReport, String> {
+    let mint = parse_pubkey(mint)?;
+    let client = rpc_client();
+    let info = fetch_mint_info(&client, &mint).await?;
+    let top10_share = fetch_top10_share(&client, &mint, info.supply).await.ok();
+    let now_ms = Utc::now().timestamp_millis();
+    Ok(SafetyReport {
+        mint: info,
+        top10_share,
+        liquidity_usd: pair.and_then(|p| p.liquidity.as_ref()).and_then(|l| l.usd),
+        pair_age_hours: pair.and_then(|p| p.pair_created_at).map(|t| (now_ms - t).max(0) as f64 / 3_600_000.0),
+    })
+}
+
+pub fn format_safety(report: &SafetyReport) -> String {
+    let authority = |a: &Option<Pubkey>| match a {
+        Some(k) => format!("⚠️ aktif (<code>{}</code>)", short_address(&k.to_string())),
+        None => "✅ revoked".to_string(),
+    };
+    let mut out = format!(
+        "🛡 <b>SAFETY CHECK:</b>\n• Risk Score: {} <code>{}/100</code>\n• Program: <code>{}</code>\n• Mint Authority: {}\n• Freeze Authority: {}\n• Supply: <code>{}</code> ({} desimal)\n• Top 10 Holder: <code>{}</code>\n• Umur Pair: <code>{}</code>",
+        report.risk_label(), report.risk_score(), report.mint.program,
+        authority(&report.mint.mint_authority), authority(&report.mint.freeze_authority),
+        format_compact(report.mint.ui_supply()), report.mint.decimals,
+        report.top10_share.map(|s| format!("{:.2}%", s)).unwrap_or_else(|| "N/A".to_string()),
+        report.pair_age_hours.map(|h| if h < 48.0 { format!("{:.0} jam", h) } else { format!("{:.0} hari", h / 24.0) }).unwrap_or_else(|| "N/A".to_string())
+    );
+    let findings = report.findings();
+    if !findings.is_empty() {
+        out.push_str("\n\n🚩 <b>RED FLAGS:</b>");
+        for (points, text) in findings {
+            out.push_str(&format!("\n• {} <i>(+{})</i>", text, points));
+        }
+    }
+    out
+}
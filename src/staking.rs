@@ -0,0 +1,214 @@
+use crate::api::{format_compact, USD_IDR};
+use crate::solana::{self, short_address};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::stake::{self, state::StakeStateV2};
+use std::collections::HashMap;
+
+const REWARD_EPOCHS: u64 = 3;
+// Byte offsets of Meta.authorized.{staker,withdrawer} in a stake account.
+const STAKER_OFFSET: usize = 12;
+const WITHDRAWER_OFFSET: usize = 44;
+const SLOT_SECONDS: f64 = 0.4;
+
+// ==========================================
+// STAKE ACCOUNTS
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StakeStatus { Activating, Active, Deactivating, Inactive, Undelegated }
+
+impl StakeStatus {
+    // Ignores warmup/cooldown rate limits, which only matter for very large stake moves.
+    fn from_epochs(activation: u64, deactivation: u64, current: u64) -> Self {
+        if deactivation != u64::MAX {
+            if deactivation >= current { StakeStatus::Deactivating } else { StakeStatus::Inactive }
+        } else if activation >= current {
+            StakeStatus::Activating
+        } else {
+            StakeStatus::Active
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StakeStatus::Activating => "🟡 Activating",
+            StakeStatus::Active => "🟢 Active",
+            StakeStatus::Deactivating => "🟠 Deactivating",
+            StakeStatus::Inactive => "⚪️ Inactive",
+            StakeStatus::Undelegated => "⚪️ Belum didelegasi",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Validator {
+    pub vote_account: String,
+    pub commission: u8,
+    pub delinquent: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StakeAccount {
+    pub address: String,
+    pub lamports: u64,
+    pub delegated: u64,
+    pub status: StakeStatus,
+    pub validator: Option<Validator>,
+    // (epoch, lamports), newest first
+    pub rewards: Vec<(u64, u64)>,
+}
+
+impl StakeAccount {
+    pub fn total_rewards(&self) -> u64 {
+        self.rewards.iter().map(|r| r.1).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StakingOverview {
+    pub owner: String,
+    pub epoch: u64,
+    pub slots_in_epoch: u64,
+    pub accounts: Vec<StakeAccount>,
+}
+
+impl StakingOverview {
+    pub fn total_lamports(&self) -> u64 {
+        self.accounts.iter().map(|a| a.lamports).sum()
+    }
+
+    pub fn total_rewards(&self) -> u64 {
+        self.accounts.iter().map(|a| a.total_rewards()).sum()
+    }
+
+    // Annualised from the average reward per epoch over the delegated stake.
+    pub fn estimated_apy(&self) -> Option<f64> {
+        let delegated: u64 = self.accounts.iter().filter(|a| !a.rewards.is_empty()).map(|a| a.delegated).sum();
+        let epochs = self.accounts.iter().map(|a| a.rewards.len()).max().unwrap_or(0);
+        if delegated == 0 || epochs == 0 || self.slots_in_epoch == 0 { return None; }
+        let per_epoch = self.total_rewards() as f64 / epochs as f64 / delegated as f64;
+        let epochs_per_year = 365.0 * 86_400.0 / (self.slots_in_epoch as f64 * SLOT_SECONDS);
+        Some(((1.0 + per_epoch).powf(epochs_per_year) - 1.0) * 100.0)
+    }
+}
+
+async fn find_stake_accounts(client: &RpcClient, owner: &Pubkey) -> Result<Vec<(Pubkey, u64, StakeStateV2)>, String> {
+    let mut found: HashMap<Pubkey, (u64, StakeStateV2)> = HashMap::new();
+    for offset in [STAKER_OFFSET, WITHDRAWER_OFFSET] {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, &owner.to_bytes()))]),
+            account_config: RpcAccountInfoConfig { encoding: Some(UiAccountEncoding::Base64), ..Default::default() },
+            ..Default::default()
+        };
+        let accounts = client.get_program_accounts_with_config(&stake::program::id(), config).await
+            .map_err(|e| format!("RPC error: {}", e))?;
+        for (key, account) in accounts {
+            if let Ok(state) = bincode::deserialize::<StakeStateV2>(&account.data) {
+                found.insert(key, (account.lamports, state));
+            }
+        }
+    }
+    Ok(found.into_iter().map(|(k, (l, s))| (k, l, s)).collect())
+}
+
+pub async fn fetch_staking(address: &str) -> Result<StakingOverview, String> {
+    let owner = solana::parse_pubkey(address)?;
+    let client = solana::rpc_client();
+    let epoch = client.get_epoch_info().await.map_err(|e| format!("RPC error: {}", e))?;
+    let stakes = find_stake_accounts(&client, &owner).await?;
+
+    let validators: HashMap<String, Validator> = match client.get_vote_accounts().await {
+        Ok(status) => {
+            let current = status.current.into_iter().map(|v| (v, false));
+            let delinquent = status.delinquent.into_iter().map(|v| (v, true));
+            current.chain(delinquent)
+                .map(|(v, delinquent)| (v.vote_pubkey.clone(), Validator { vote_account: v.vote_pubkey, commission: v.commission, delinquent }))
+                .collect()
+        }
+        Err(_) => HashMap::new(),
+    };
+
+    let keys: Vec<Pubkey> = stakes.iter().map(|s| s.0).collect();
+    let mut rewards: HashMap<Pubkey, Vec<(u64, u64)>> = HashMap::new();
+    if !keys.is_empty() {
+        for back in 1..=REWARD_EPOCHS.min(epoch.epoch) {
+            let target = epoch.epoch - back;
+            let Ok(results) = client.get_inflation_reward(&keys, Some(target)).await else { break };
+            for (key, reward) in keys.iter().zip(results) {
+                if let Some(r) = reward {
+                    rewards.entry(*key).or_default().push((r.epoch, r.amount));
+                }
+            }
+        }
+    }
+
+    let mut accounts: Vec<StakeAccount> = stakes.into_iter().map(|(key, lamports, state)| {
+        let (delegated, status, validator) = match state {
+            StakeStateV2::Stake(_, stake, _) => {
+                let d = stake.delegation;
+                let vote = d.voter_pubkey.to_string();
+                let validator = validators.get(&vote).cloned()
+                    .or(Some(Validator { vote_account: vote, commission: 0, delinquent: false }));
+                (d.stake, StakeStatus::from_epochs(d.activation_epoch, d.deactivation_epoch, epoch.epoch), validator)
+            }
+            _ => (0, StakeStatus::Undelegated, None),
+        };
+        StakeAccount {
+            address: key.to_string(),
+            lamports,
+            delegated,
+            status,
+            validator,
+            rewards: rewards.remove(&key).unwrap_or_default(),
+        }
+    }).collect();
+    accounts.sort_by_key(|a| std::cmp::Reverse(a.lamports));
+
+    Ok(StakingOverview { owner: owner.to_string(), epoch: epoch.epoch, slots_in_epoch: epoch.slots_in_epoch, accounts })
+}
+
+pub fn format_staking(overview: &StakingOverview, sol_price: Option<f64>) -> String {
+    let value = |lamports: u64| -> String {
+        let sol = lamports_to_sol(lamports);
+        match sol_price {
+            Some(p) => format!("<code>{:.4} SOL</code> ≈ <code>${:.2}</code> • <code>Rp {}</code>", sol, sol * p, format_compact(sol * p * USD_IDR)),
+            None => format!("<code>{:.4} SOL</code>", sol),
+        }
+    };
+    let mut out = format!(
+        "🥩 <b>SOLANA STAKING</b>\n========================\n👤 Wallet: <code>{}</code>\n🗓 Epoch: <code>{}</code>\n\n💰 <b>TOTAL:</b>\n• Stake: {}\n• Reward {} epoch: {}\n• Est. APY: <code>{}</code>\n",
+        overview.owner, overview.epoch, value(overview.total_lamports()),
+        REWARD_EPOCHS, value(overview.total_rewards()),
+        overview.estimated_apy().map(|a| format!("{:.2}%", a)).unwrap_or_else(|| "N/A".to_string())
+    );
+
+    if overview.accounts.is_empty() {
+        out.push_str("\n<i>Tidak ada stake account untuk wallet ini</i>\n");
+    }
+    for (i, a) in overview.accounts.iter().take(10).enumerate() {
+        out.push_str(&format!(
+            "\n{}. <a href=\"https://solscan.io/account/{}\">{}</a> • {}\n• Saldo: {}\n• Delegasi: <code>{:.4} SOL</code>\n",
+            i + 1, a.address, short_address(&a.address), a.status.label(), value(a.lamports), lamports_to_sol(a.delegated)
+        ));
+        if let Some(v) = &a.validator {
+            out.push_str(&format!(
+                "• Validator: <code>{}</code> • Komisi <code>{}%</code>{}\n",
+                short_address(&v.vote_account), v.commission, if v.delinquent { " • ⚠️ DELINQUENT" } else { "" }
+            ));
+        }
+        if !a.rewards.is_empty() {
+            let epochs: Vec<String> = a.rewards.iter().map(|(e, l)| format!("E{} <code>+{:.5}</code>", e, lamports_to_sol(*l))).collect();
+            out.push_str(&format!("• Reward: {}\n", epochs.join(" • ")));
+        }
+    }
+    if overview.accounts.len() > 10 {
+        out.push_str(&format!("\n<i>+{} stake account lainnya</i>\n", overview.accounts.len() - 10));
+    }
+    out.push_str("========================\n<i>💡 Reward dari getInflationReward • APY estimasi, bukan jaminan</i>");
+    out
+}
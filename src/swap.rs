@@ -0,0 +1,542 @@
+use crate::api::{escape_html, format_compact, format_number};
+use crate::signer::{self, TxSigner};
+use crate::solana::{self, short_address, WSOL_MINT};
+use base64::Engine;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountData, UiAccountEncoding};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+pub const QUOTE_TTL: i64 = 60;
+pub const MAX_SLIPPAGE_BPS: u16 = 1000;
+pub const AMOUNT_PRESETS: [f64; 4] = [0.05, 0.1, 0.25, 0.5];
+// Network and priority fees plus rent for the token and wrapped-SOL accounts a swap may open.
+pub const FEE_RENT_BUDGET_LAMPORTS: u64 = 10_000_000;
+
+// ==========================================
+// CONFIG & LIMITS
+// ==========================================
+
+// Any Jupiter v6 compatible endpoint works, e.g. a local stub server when testing against solana-test-validator.
+pub fn quote_api_url() -> String {
+    std::env::var("JUPITER_API_URL").unwrap_or_else(|_| "https://quote-api.jup.ag/v6".to_string()).trim_end_matches('/').to_string()
+}
+
+pub fn default_slippage_bps() -> u16 {
+    std::env::var("REAL_SLIPPAGE_BPS").ok().and_then(|v| v.parse().ok()).unwrap_or(100).clamp(1, MAX_SLIPPAGE_BPS)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SwapLimits {
+    pub max_trade_sol: f64,
+    pub daily_cap_sol: f64,
+}
+
+impl SwapLimits {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: f64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self { max_trade_sol: read("REAL_MAX_TRADE_SOL", 0.5), daily_cap_sol: read("REAL_DAILY_CAP_SOL", 2.0) }
+    }
+
+    pub fn check(&self, lamports: u64, spent_today: u64) -> Result<(), String> {
+        let sol = lamports_to_sol(lamports);
+        if sol > self.max_trade_sol {
+            return Err(format!("Melebihi batas per transaksi ({} SOL)", self.max_trade_sol));
+        }
+        if lamports_to_sol(spent_today) + sol > self.daily_cap_sol {
+            return Err(format!(
+                "Melebihi batas harian ({} SOL, terpakai {:.4} SOL)",
+                self.daily_cap_sol, lamports_to_sol(spent_today)
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DailySpend {
+    day: Option<NaiveDate>,
+    lamports: u64,
+}
+
+impl DailySpend {
+    pub fn spent(&self, today: NaiveDate) -> u64 {
+        if self.day == Some(today) { self.lamports } else { 0 }
+    }
+
+    pub fn record(&mut self, today: NaiveDate, lamports: u64) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.lamports = 0;
+        }
+        self.lamports += lamports;
+    }
+}
+
+// ==========================================
+// QUOTE
+// ==========================================
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuoteInfo {
+    #[serde(rename = "inputMint")]
+    pub input_mint: String,
+    #[serde(rename = "outputMint")]
+    pub output_mint: String,
+    #[serde(rename = "inAmount")]
+    pub in_amount: String,
+    #[serde(rename = "outAmount")]
+    pub out_amount: String,
+    #[serde(rename = "otherAmountThreshold")]
+    pub min_out_amount: String,
+    #[serde(rename = "slippageBps")]
+    pub slippage_bps: u16,
+    #[serde(rename = "priceImpactPct", default)]
+    pub price_impact_pct: Option<String>,
+    #[serde(rename = "routePlan", default)]
+    pub route_plan: Vec<RoutePlan>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RoutePlan {
+    #[serde(rename = "swapInfo")]
+    pub swap_info: SwapInfo,
+    #[serde(default)]
+    pub percent: u8,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SwapInfo {
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(rename = "feeAmount", default)]
+    pub fee_amount: Option<String>,
+    #[serde(rename = "feeMint", default)]
+    pub fee_mint: Option<String>,
+}
+
+// The raw response is kept so it can be posted back to /swap unchanged.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub raw: Value,
+    pub info: QuoteInfo,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingSwap {
+    pub mint: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub token_program: Pubkey,
+    pub lamports: u64,
+    pub quote: Quote,
+    pub created: i64,
+}
+
+impl PendingSwap {
+    pub fn expired(&self, now: i64) -> bool {
+        now - self.created > QUOTE_TTL
+    }
+}
+
+pub async fn fetch_quote(output_mint: &str, lamports: u64, slippage_bps: u16) -> Result<Quote, String> {
+    let url = format!(
+        "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+        quote_api_url(), WSOL_MINT, output_mint, lamports, slippage_bps
+    );
+    let response = reqwest::Client::new().get(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Quote gagal (HTTP {})", response.status().as_u16()));
+    }
+    let raw: Value = response.json().await.map_err(|_| "Gagal parse quote".to_string())?;
+    let info: QuoteInfo = serde_json::from_value(raw.clone()).map_err(|_| "Format quote tidak dikenal".to_string())?;
+    if info.input_mint != WSOL_MINT || info.output_mint != output_mint || info.in_amount != lamports.to_string() {
+        return Err("Quote tidak sesuai permintaan (mint/jumlah berbeda)".to_string());
+    }
+    Ok(Quote { raw, info })
+}
+
+fn ui_amount(raw: &str, decimals: u8) -> f64 {
+    raw.parse::<f64>().unwrap_or(0.0) / 10f64.powi(decimals as i32)
+}
+
+pub fn format_quote(pending: &PendingSwap) -> String {
+    let info = &pending.quote.info;
+    let symbol = escape_html(&pending.symbol);
+    let fee = |s: &SwapInfo| -> String {
+        let amount = s.fee_amount.as_deref().unwrap_or("0");
+        match s.fee_mint.as_deref() {
+            Some(WSOL_MINT) => format!("{:.6} SOL", ui_amount(amount, 9)),
+            Some(m) if m == pending.mint => format!("{} {}", format_compact(ui_amount(amount, pending.decimals)), symbol),
+            Some(m) => format!("{} ({})", amount, short_address(m)),
+            None => "-".to_string(),
+        }
+    };
+
+    let mut out = format!(
+        "🚀 <b>REAL BUY QUOTE</b>\n========================\n🪙 <b>{}</b> (<code>{}</code>)\n\n💸 <b>SWAP:</b>\n• Bayar: <code>{:.4} SOL</code>\n• Estimasi: <code>{} {}</code>\n• Minimum: <code>{} {}</code>\n• Slippage: <code>{:.2}%</code>\n• Price Impact: <code>{:.2}%</code>\n\n🛣 <b>ROUTE:</b>\n",
+        symbol, short_address(&pending.mint),
+        lamports_to_sol(pending.lamports),
+        format_compact(ui_amount(&info.out_amount, pending.decimals)), symbol,
+        format_compact(ui_amount(&info.min_out_amount, pending.decimals)), symbol,
+        info.slippage_bps as f64 / 100.0,
+        info.price_impact_pct.as_deref().and_then(|p| p.parse::<f64>().ok()).unwrap_or(0.0) * 100.0
+    );
+    for hop in &info.route_plan {
+        out.push_str(&format!(
+            "• {} <code>{}%</code> • fee <code>{}</code>\n",
+            escape_html(hop.swap_info.label.as_deref().unwrap_or("Unknown")), hop.percent, fee(&hop.swap_info)
+        ));
+    }
+    out.push_str(&format!(
+        "========================\n{}\n<i>💡 Quote berlaku {} detik • Network fee ditambahkan saat eksekusi</i>",
+        if dry_run_forced() { "🧪 <b>DRY-RUN MODE</b>: confirm hanya menjalankan simulasi." } else { "⚠️ <b>Transaksi NYATA</b> dari wallet bot." },
+        QUOTE_TTL
+    ));
+    out
+}
+
+// ==========================================
+// EXECUTION
+// ==========================================
+
+#[derive(Deserialize, Debug)]
+struct SwapResponse {
+    #[serde(rename = "swapTransaction")]
+    swap_transaction: String,
+}
+
+pub async fn build_transaction(quote: &Quote, user: &Pubkey) -> Result<VersionedTransaction, String> {
+    let body = json!({
+        "quoteResponse": quote.raw,
+        "userPublicKey": user.to_string(),
+        "wrapAndUnwrapSol": true,
+        "dynamicComputeUnitLimit": true,
+    });
+    let response = reqwest::Client::new().post(format!("{}/swap", quote_api_url()))
+        .json(&body)
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Build swap gagal (HTTP {})", response.status().as_u16()));
+    }
+    let data: SwapResponse = response.json().await.map_err(|_| "Gagal parse transaksi swap".to_string())?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(&data.swap_transaction)
+        .map_err(|_| "Transaksi swap tidak valid".to_string())?;
+    bincode::deserialize(&bytes).map_err(|_| "Transaksi swap tidak valid".to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub err: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub sol_delta: Option<i64>,
+    pub token_delta: Option<f64>,
+}
+
+fn token_ui_amount(account: &UiAccount) -> Option<f64> {
+    let UiAccountData::Json(parsed) = &account.data else { return None };
+    parsed.parsed["info"]["tokenAmount"]["uiAmountString"].as_str().and_then(|v| v.parse().ok())
+}
+
+// Balance deltas compare the current on-chain state with the post-simulation state of the payer and its token account.
+pub async fn simulate(client: &RpcClient, tx: &VersionedTransaction, payer: &Pubkey, token_account: &Pubkey) -> Result<SimulationReport, String> {
+    let pre_lamports = client.get_balance(payer).await.ok();
+    let pre_tokens = client.get_token_account_balance(token_account).await.ok()
+        .and_then(|b| b.ui_amount_string.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    let config = RpcSimulateTransactionConfig {
+        commitment: Some(CommitmentConfig::confirmed()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::JsonParsed),
+            addresses: vec![payer.to_string(), token_account.to_string()],
+        }),
+        ..Default::default()
+    };
+    let result = client.simulate_transaction_with_config(tx, config).await.map_err(|e| format!("RPC error: {}", e))?.value;
+
+    let accounts = result.accounts.unwrap_or_default();
+    let post_lamports = accounts.first().and_then(|a| a.as_ref()).map(|a| a.lamports);
+    let post_tokens = match accounts.get(1) {
+        Some(Some(account)) => token_ui_amount(account),
+        Some(None) => Some(0.0),
+        None => None,
+    };
+
+    Ok(SimulationReport {
+        err: result.err.map(|e| e.to_string()),
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        sol_delta: pre_lamports.zip(post_lamports).map(|(pre, post)| post as i64 - pre as i64),
+        token_delta: post_tokens.map(|post| post - pre_tokens),
+    })
+}
+
+// The /swap transaction comes from a remote service, so the simulated balance changes must match the
+// quote before it is signed off: SOL spent within the quoted amount plus fees and rent, and at least
+// the quoted minimum of the token received.
+pub fn verify_simulation(pending: &PendingSwap, report: &SimulationReport) -> Result<(), String> {
+    let budget = (pending.lamports + FEE_RENT_BUDGET_LAMPORTS) as i64;
+    match report.sol_delta {
+        Some(delta) if delta >= -budget => {}
+        Some(delta) => return Err(format!(
+            "Simulasi memakai {:.6} SOL, melebihi quote {:.4} SOL + biaya", -delta as f64 / 1e9, lamports_to_sol(pending.lamports)
+        )),
+        None => return Err("Perubahan saldo SOL tidak bisa diverifikasi".to_string()),
+    }
+    let min_out = ui_amount(&pending.quote.info.min_out_amount, pending.decimals);
+    match report.token_delta {
+        Some(delta) if delta > 0.0 && delta >= min_out * (1.0 - 1e-9) => Ok(()),
+        Some(delta) => Err(format!(
+            "Simulasi hanya menerima {} {}, minimum quote {}", format_compact(delta), pending.symbol, format_compact(min_out)
+        )),
+        None => Err("Perubahan saldo token tidak bisa diverifikasi".to_string()),
+    }
+}
+
+// Builds, signs and simulates; nothing is broadcast until `send` is called.
+pub async fn prepare(client: &RpcClient, pending: &PendingSwap, signer: &dyn TxSigner) -> Result<(VersionedTransaction, SimulationReport), String> {
+    let payer = signer.pubkey();
+    let tx = signer::sign_transaction(signer, build_transaction(&pending.quote, &payer).await?).await?;
+    let mint = solana::parse_pubkey(&pending.mint)?;
+    let token_account = solana::associated_token_address(&payer, &mint, &pending.token_program);
+    let report = simulate(client, &tx, &payer, &token_account).await?;
+    Ok((tx, report))
+}
+
+pub fn dry_run_forced() -> bool {
+    matches!(std::env::var("SOLANA_DRY_RUN").as_deref().map(str::to_lowercase).as_deref(), Ok("1" | "true" | "yes" | "on"))
+}
+
+pub async fn send(client: &RpcClient, tx: &VersionedTransaction) -> Result<Signature, String> {
+    if dry_run_forced() {
+        return Err("SOLANA_DRY_RUN aktif, transaksi tidak dikirim".to_string());
+    }
+    client.send_and_confirm_transaction(tx).await.map_err(|e| format!("Transaksi gagal: {}", e))
+}
+
+pub fn format_simulation(report: &SimulationReport, symbol: &str) -> String {
+    let status = match &report.err {
+        None => "✅ Sukses".to_string(),
+        Some(e) => format!("❌ Gagal: {}", escape_html(e)),
+    };
+    let logs = report.logs.iter().rev().take(12).rev()
+        .map(|l| escape_html(&l.chars().take(120).collect::<String>()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "🧪 <b>DRY RUN</b>\n========================\n• Status: {}\n• Compute Units: <code>{}</code>\n\n⚖️ <b>BALANCE DELTA:</b>\n• SOL: <code>{}</code>\n• {}: <code>{}</code>\n\n📜 <b>LOGS</b> ({} baris, {} terakhir):\n<pre>{}</pre>\n========================\n<i>ℹ️ Tidak ada transaksi yang dikirim</i>",
+        status,
+        report.units_consumed.map(|u| format_number(u as f64)).unwrap_or_else(|| "N/A".to_string()),
+        report.sol_delta.map(|d| format!("{:+.6}", d as f64 / 1e9)).unwrap_or_else(|| "N/A".to_string()),
+        escape_html(symbol),
+        report.token_delta.map(|d| if d < 0.0 { format!("-{}", format_compact(-d)) } else { format!("+{}", format_compact(d)) }).unwrap_or_else(|| "N/A".to_string()),
+        report.logs.len(), report.logs.len().min(12),
+        if logs.is_empty() { "-".to_string() } else { logs }
+    )
+}
+
+pub fn format_receipt(pending: &PendingSwap, signature: &Signature) -> String {
+    format!(
+        "✅ <b>SWAP CONFIRMED</b>\n========================\n• Bayar: <code>{:.4} SOL</code>\n• Estimasi: <code>{} {}</code>\n• Signature: <code>{}</code>\n========================\n<a href=\"https://solscan.io/tx/{}\">🔍 Lihat di Solscan</a>",
+        lamports_to_sol(pending.lamports),
+        format_compact(ui_amount(&pending.quote.info.out_amount, pending.decimals)), escape_html(&pending.symbol),
+        signature, signature
+    )
+}
+
+// The validator test needs a running `solana-test-validator`:
+//   solana-test-validator --reset --quiet &
+//   SOLANA_RPC_URL=http://127.0.0.1:8899 cargo test swap -- --ignored
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const STUB_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    // JUPITER_API_URL is process-wide, so tests that point it at their own stub run one at a time.
+    static ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    struct TestSigner(Keypair);
+
+    #[async_trait::async_trait]
+    impl TxSigner for TestSigner {
+        fn pubkey(&self) -> Pubkey {
+            self.0.pubkey()
+        }
+
+        fn source(&self) -> &'static str {
+            "test"
+        }
+
+        async fn sign_message(&self, message: &[u8]) -> Result<Signature, String> {
+            Ok(self.0.sign_message(message))
+        }
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> (String, String) {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap_or(0);
+            if n == 0 { break; }
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end].lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+                    .unwrap_or(0);
+                if buf.len() >= end + 4 + length { break; }
+            }
+        }
+        let text = String::from_utf8_lossy(&buf).to_string();
+        let (head, body) = text.split_once("\r\n\r\n").unwrap_or((&text, ""));
+        (head.lines().next().unwrap_or("").to_string(), body.to_string())
+    }
+
+    // Minimal Jupiter v6 stand-in: /quote returns a fixed single-hop route and /swap returns a
+    // 1-lamport self-transfer for the requested user, so signing and simulation run unchanged.
+    fn stub_response(request_line: &str, body: &str, blockhash: Hash) -> String {
+        if request_line.starts_with("GET /quote") {
+            json!({
+                "inputMint": WSOL_MINT, "outputMint": STUB_MINT, "inAmount": "10000000",
+                "outAmount": "1500000", "otherAmountThreshold": "1485000", "slippageBps": 100,
+                "priceImpactPct": "0.0012",
+                "routePlan": [{ "swapInfo": { "label": "Stub AMM", "feeAmount": "25000", "feeMint": WSOL_MINT }, "percent": 100 }],
+            }).to_string()
+        } else {
+            let request: Value = serde_json::from_str(body).unwrap_or_default();
+            let user: Pubkey = request["userPublicKey"].as_str().and_then(|k| k.parse().ok()).unwrap_or_default();
+            let message = Message::new_with_blockhash(&[system_instruction::transfer(&user, &user, 1)], Some(&user), &blockhash);
+            let tx = VersionedTransaction { signatures: vec![Signature::default()], message: VersionedMessage::Legacy(message) };
+            let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+            json!({ "swapTransaction": encoded }).to_string()
+        }
+    }
+
+    async fn spawn_stub_quote_server(blockhash: Hash) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (request_line, body) = read_request(&mut socket).await;
+                    let payload = stub_response(&request_line, &body, blockhash);
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        payload.len(), payload
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn pending(quote: Quote) -> PendingSwap {
+        PendingSwap {
+            mint: STUB_MINT.to_string(), symbol: "STUB".to_string(), decimals: 6, token_program: spl_token::id(),
+            lamports: 10_000_000, quote, created: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    #[tokio::test]
+    async fn quote_and_swap_against_stub_server() {
+        let _env = ENV_LOCK.lock().await;
+        std::env::set_var("JUPITER_API_URL", spawn_stub_quote_server(Hash::new_unique()).await);
+
+        let quote = fetch_quote(STUB_MINT, 10_000_000, 100).await.unwrap();
+        assert_eq!(quote.info.out_amount, "1500000");
+        assert_eq!(quote.info.route_plan[0].swap_info.label.as_deref(), Some("Stub AMM"));
+        assert!(format_quote(&pending(quote.clone())).contains("Stub AMM"));
+
+        let signer = TestSigner(Keypair::new());
+        let tx = build_transaction(&quote, &signer.pubkey()).await.unwrap();
+        let signed = signer::sign_transaction(&signer, tx).await.unwrap();
+        assert!(signed.signatures[0].verify(signer.pubkey().as_ref(), &signed.message.serialize()));
+    }
+
+    #[tokio::test]
+    async fn quote_for_a_different_amount_is_rejected() {
+        let _env = ENV_LOCK.lock().await;
+        std::env::set_var("JUPITER_API_URL", spawn_stub_quote_server(Hash::new_unique()).await);
+        assert!(fetch_quote(STUB_MINT, 20_000_000, 100).await.is_err());
+        assert!(fetch_quote(WSOL_MINT, 10_000_000, 100).await.is_err());
+    }
+
+    fn report(sol_delta: Option<i64>, token_delta: Option<f64>) -> SimulationReport {
+        SimulationReport { err: None, logs: Vec::new(), units_consumed: None, sol_delta, token_delta }
+    }
+
+    fn stub_quote() -> Quote {
+        let raw = json!({
+            "inputMint": WSOL_MINT, "outputMint": STUB_MINT, "inAmount": "10000000",
+            "outAmount": "1500000", "otherAmountThreshold": "1485000", "slippageBps": 100,
+        });
+        Quote { info: serde_json::from_value(raw.clone()).unwrap(), raw }
+    }
+
+    #[test]
+    fn simulation_must_match_the_quote() {
+        let swap = pending(stub_quote());
+        // 0.01 SOL in, fees and rent on top, 1.49 tokens out against a 1.485 minimum.
+        assert!(verify_simulation(&swap, &report(Some(-12_100_000), Some(1.49))).is_ok());
+        assert!(verify_simulation(&swap, &report(Some(-10_000_000 - FEE_RENT_BUDGET_LAMPORTS as i64 - 1), Some(1.49))).is_err());
+        assert!(verify_simulation(&swap, &report(Some(-12_100_000), Some(1.0))).is_err());
+        assert!(verify_simulation(&swap, &report(Some(-12_100_000), Some(0.0))).is_err());
+        assert!(verify_simulation(&swap, &report(None, Some(1.49))).is_err());
+        assert!(verify_simulation(&swap, &report(Some(-12_100_000), None)).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs solana-test-validator at SOLANA_RPC_URL"]
+    async fn dry_run_and_send_against_local_validator() {
+        let _env = ENV_LOCK.lock().await;
+        if std::env::var("SOLANA_RPC_URL").is_err() {
+            std::env::set_var("SOLANA_RPC_URL", "http://127.0.0.1:8899");
+        }
+        std::env::remove_var("SOLANA_DRY_RUN");
+        let client = solana::rpc_client();
+        let signer = TestSigner(Keypair::new());
+        let airdrop = client.request_airdrop(&signer.pubkey(), 1_000_000_000).await.unwrap();
+        for _ in 0..30 {
+            if client.confirm_transaction(&airdrop).await.unwrap_or(false) { break; }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+
+        let blockhash = client.get_latest_blockhash().await.unwrap();
+        std::env::set_var("JUPITER_API_URL", spawn_stub_quote_server(blockhash).await);
+        let swap = pending(fetch_quote(STUB_MINT, 10_000_000, 100).await.unwrap());
+
+        let (tx, report) = prepare(&client, &swap, &signer).await.unwrap();
+        assert_eq!(report.err, None, "logs: {:?}", report.logs);
+        assert!(report.sol_delta.is_some_and(|d| d < 0), "fee should be charged: {:?}", report.sol_delta);
+        assert!(format_simulation(&report, &swap.symbol).contains("Sukses"));
+        // The stub swap is a self-transfer, so the bot would refuse it: no tokens arrive.
+        assert!(verify_simulation(&swap, &report).is_err());
+
+        let signature = send(&client, &tx).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+    }
+}
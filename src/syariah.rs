@@ -0,0 +1,237 @@
+use crate::api::SyariahApiResponse;
+
+// ==========================================
+// SCREENING STANDARDS
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Standard { OjkDsnMui, Aaoifi, Custom }
+
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    pub max_debt: f64,
+    pub max_non_halal: f64,
+    pub max_cash_receivables: Option<f64>,
+}
+
+impl Standard {
+    pub const ALL: [Standard; 3] = [Standard::OjkDsnMui, Standard::Aaoifi, Standard::Custom];
+
+    pub fn code(&self) -> &'static str {
+        match self { Standard::OjkDsnMui => "ojk", Standard::Aaoifi => "aaoifi", Standard::Custom => "custom" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.code() == s)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self { Standard::OjkDsnMui => "OJK / DSN-MUI", Standard::Aaoifi => "AAOIFI", Standard::Custom => "LUBIX CUSTOM" }
+    }
+
+    /// Limits in percent. The custom set is read from `SYARIAH_CUSTOM_MAX_DEBT`
+    /// and `SYARIAH_CUSTOM_MAX_NON_HALAL` so the team can tune it without a rebuild.
+    pub fn thresholds(&self) -> Thresholds {
+        match self {
+            Standard::OjkDsnMui => Thresholds { max_debt: 45.0, max_non_halal: 10.0, max_cash_receivables: None },
+            Standard::Aaoifi => Thresholds { max_debt: 30.0, max_non_halal: 5.0, max_cash_receivables: Some(30.0) },
+            Standard::Custom => Thresholds {
+                max_debt: env_percent("SYARIAH_CUSTOM_MAX_DEBT", 33.0),
+                max_non_halal: env_percent("SYARIAH_CUSTOM_MAX_NON_HALAL", 5.0),
+                max_cash_receivables: None,
+            },
+        }
+    }
+
+    /// AAOIFI divides by market capitalisation; OJK/DSN-MUI and the custom set divide by total assets.
+    pub fn uses_market_cap(&self) -> bool {
+        *self == Standard::Aaoifi
+    }
+}
+
+fn env_percent(key: &str, default: f64) -> f64 {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+}
+
+// ==========================================
+// SCREENING RESULT
+// ==========================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict { Pass, Fail, Unknown }
+
+impl Verdict {
+    pub fn emoji(&self) -> &'static str {
+        match self { Verdict::Pass => "✅", Verdict::Fail => "❌", Verdict::Unknown => "❔" }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Criterion {
+    pub label: &'static str,
+    pub value: Option<f64>,
+    pub limit: f64,
+}
+
+impl Criterion {
+    pub fn verdict(&self) -> Verdict {
+        match self.value {
+            Some(v) if v < self.limit => Verdict::Pass,
+            Some(_) => Verdict::Fail,
+            None => Verdict::Unknown,
+        }
+    }
+
+    /// Distance to the limit in percentage points; negative once breached.
+    pub fn margin(&self) -> Option<f64> {
+        self.value.map(|v| self.limit - v)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreeningResult {
+    pub standard: Standard,
+    pub business: Option<bool>,
+    pub criteria: Vec<Criterion>,
+}
+
+impl ScreeningResult {
+    pub fn business_verdict(&self) -> Verdict {
+        match self.business { Some(true) => Verdict::Pass, Some(false) => Verdict::Fail, None => Verdict::Unknown }
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        let verdicts: Vec<Verdict> = std::iter::once(self.business_verdict())
+            .chain(self.criteria.iter().map(|c| c.verdict()))
+            .collect();
+        if verdicts.contains(&Verdict::Fail) {
+            Verdict::Fail
+        } else if verdicts.contains(&Verdict::Unknown) {
+            Verdict::Unknown
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+pub fn indicator_value(v: &Option<serde_json::Value>) -> Option<f64> {
+    match v.as_ref()? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().trim_end_matches('%').parse().ok(),
+        _ => None,
+    }
+}
+
+// Amount as a percentage of market cap; unknown when either side is missing.
+fn market_cap_ratio(amount: Option<f64>, market_cap: Option<f64>) -> Option<f64> {
+    match (amount, market_cap) {
+        (Some(a), Some(c)) if c > 0.0 => Some(a / c * 100.0),
+        _ => None,
+    }
+}
+
+pub fn screen(standard: Standard, stock: &SyariahApiResponse) -> ScreeningResult {
+    let limits = standard.thresholds();
+    let indicator = stock.syariah_indicator.as_ref();
+    let debt_to_assets = indicator.and_then(|i| indicator_value(&i.hutang_bunga));
+    let non_halal = indicator.and_then(|i| indicator_value(&i.non_halal));
+
+    let mut criteria = Vec::new();
+    if standard.uses_market_cap() {
+        // The API reports debt as a share of total assets, so the amount is rebuilt from total assets.
+        let total_assets = indicator.and_then(|i| i.total_aset);
+        let debt = debt_to_assets.zip(total_assets).map(|(ratio, assets)| ratio / 100.0 * assets);
+        criteria.push(Criterion { label: "Hutang Berbunga / Market Cap", value: market_cap_ratio(debt, stock.market_cap), limit: limits.max_debt });
+    } else {
+        criteria.push(Criterion { label: "Hutang Berbasis Bunga", value: debt_to_assets, limit: limits.max_debt });
+    }
+    criteria.push(Criterion { label: "Pendapatan Non-Halal", value: non_halal, limit: limits.max_non_halal });
+    if let Some(limit) = limits.max_cash_receivables {
+        let cash_receivables = indicator.and_then(|i| i.kas_piutang);
+        criteria.push(Criterion { label: "Kas & Piutang / Market Cap", value: market_cap_ratio(cash_receivables, stock.market_cap), limit });
+    }
+
+    ScreeningResult { standard, business: indicator.and_then(|i| i.business), criteria }
+}
+
+pub fn format_report(result: &ScreeningResult) -> String {
+    let overall = match result.verdict() {
+        Verdict::Pass => "✅ <b>LOLOS</b>",
+        Verdict::Fail => "❌ <b>TIDAK LOLOS</b>",
+        Verdict::Unknown => "⚠️ <b>DATA TIDAK LENGKAP</b>",
+    };
+
+    let mut lines = vec![
+        format!("📐 <b>SCREENING {}</b>", result.standard.name()),
+        format!("• Hasil: {}", overall),
+        format!("{} Kegiatan Usaha: <code>{}</code>", result.business_verdict().emoji(), match result.business {
+            Some(true) => "Sesuai syariah",
+            Some(false) => "Tidak sesuai",
+            None => "N/A",
+        }),
+    ];
+
+    for c in &result.criteria {
+        let detail = match (c.value, c.margin()) {
+            (Some(v), Some(m)) => format!("{:.2}% / maks {:.0}% (margin {:+.2}%)", v, c.limit, m),
+            _ => format!("N/A / maks {:.0}%", c.limit),
+        };
+        lines.push(format!("{} {}: <code>{}</code>", c.verdict().emoji(), c.label, detail));
+    }
+
+    if result.standard.uses_market_cap() {
+        lines.push("<i>ℹ️ Rasio AAOIFI dihitung terhadap market cap dari total aset, kas & piutang di API</i>".to_string());
+    }
+
+    lines.join("\n")
+}
+
+// ==========================================
+// STATUS SNAPSHOTS
+// ==========================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub issi: Option<bool>,
+    pub business: Option<bool>,
+    pub screenings: Vec<ScreeningResult>,
+}
+
+impl Snapshot {
+    pub fn from_stock(stock: &SyariahApiResponse) -> Self {
+        Self {
+            issi: stock.issi,
+            business: stock.syariah_indicator.as_ref().and_then(|i| i.business),
+            screenings: Standard::ALL.into_iter().map(|standard| screen(standard, stock)).collect(),
+        }
+    }
+}
+
+pub fn diff(prev: &Snapshot, cur: &Snapshot) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    match (prev.issi, cur.issi) {
+        (Some(true), Some(false)) => changes.push("🚨 <b>Keluar dari ISSI</b> (delisting)".to_string()),
+        (Some(false), Some(true)) => changes.push("🎉 <b>Masuk ISSI</b> (listing baru)".to_string()),
+        _ => {}
+    }
+    match (prev.business, cur.business) {
+        (Some(true), Some(false)) => changes.push("❌ Kegiatan usaha tidak lagi sesuai syariah".to_string()),
+        (Some(false), Some(true)) => changes.push("✅ Kegiatan usaha kembali sesuai syariah".to_string()),
+        _ => {}
+    }
+
+    for (before, after) in prev.screenings.iter().zip(&cur.screenings) {
+        for (b, a) in before.criteria.iter().zip(&after.criteria) {
+            if let (Some(bv), Some(av)) = (b.value, a.value) {
+                let limit = a.limit;
+                if (bv < limit) != (av < limit) {
+                    let (emoji, dir) = if av < limit { ("✅", "kembali di bawah") } else { ("⚠️", "melewati") };
+                    changes.push(format!("{} {} <code>{:.2}% → {:.2}%</code> {} batas {} ({:.0}%)", emoji, a.label, bv, av, dir, after.standard.name(), limit));
+                }
+            }
+        }
+    }
+
+    changes
+}
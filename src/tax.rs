@@ -0,0 +1,209 @@
+use crate::api::{escape_html, format_number};
+use crate::csvio::Table;
+use crate::ledger::{AssetClass, Ledger, Side};
+use crate::solana::short_address;
+use chrono::Datelike;
+use serde_json::json;
+
+// ==========================================
+// TAX RATES
+// ==========================================
+
+// Final-tax rates as fractions of the gross transaction value, keyed by tax year because the crypto
+// rules changed: no final regime before 2022, PPh 22 final 0.1% plus PPN 0.11% on purchases through
+// a registered (PFAK) exchange for 2022-2024, and PPh 22 final 0.21% without PPN from 2025. PPh final
+// 0.1% on IDX share sales applies to every year. Mid-year switches (May 2022, August 2025) use the
+// rate in force at year end. Override per year (TAX_CRYPTO_SELL_RATE_2025) or for all years.
+#[derive(Debug, Clone, Copy)]
+pub struct TaxRates {
+    pub stock_sell: f64,
+    pub crypto_sell: f64,
+    pub crypto_buy: f64,
+}
+
+impl TaxRates {
+    pub fn for_year(year: i32) -> Self {
+        let (crypto_sell, crypto_buy) = match year {
+            ..=2021 => (0.0, 0.0),
+            2022..=2024 => (0.001, 0.0011),
+            _ => (0.0021, 0.0),
+        };
+        let read = |key: &str, default: f64| {
+            std::env::var(format!("{}_{}", key, year)).or_else(|_| std::env::var(key)).ok()
+                .and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            stock_sell: read("TAX_STOCK_SELL_RATE", 0.001),
+            crypto_sell: read("TAX_CRYPTO_SELL_RATE", crypto_sell),
+            crypto_buy: read("TAX_CRYPTO_BUY_RATE", crypto_buy),
+        }
+    }
+}
+
+// ==========================================
+// YEARLY REPORT
+// ==========================================
+
+#[derive(Debug, Clone)]
+pub struct TaxLine {
+    pub date: chrono::NaiveDate,
+    pub class: AssetClass,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub gross: f64,
+    pub rate: f64,
+    pub gain: Option<f64>,
+}
+
+impl TaxLine {
+    pub fn tax(&self) -> f64 {
+        self.gross * self.rate
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassTotals {
+    pub sell_gross: f64,
+    pub buy_gross: f64,
+    pub tax: f64,
+    pub gain: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaxReport {
+    pub year: i32,
+    pub rates: TaxRates,
+    pub lines: Vec<TaxLine>,
+}
+
+impl TaxReport {
+    pub fn totals(&self, class: AssetClass) -> ClassTotals {
+        self.lines.iter().filter(|l| l.class == class).fold(ClassTotals::default(), |mut t, l| {
+            match l.side {
+                Side::Sell => t.sell_gross += l.gross,
+                Side::Buy => t.buy_gross += l.gross,
+            }
+            t.tax += l.tax();
+            t.gain += l.gain.unwrap_or(0.0);
+            t
+        })
+    }
+
+    pub fn total_tax(&self) -> f64 {
+        self.lines.iter().map(|l| l.tax()).sum()
+    }
+}
+
+fn sell_rate(class: AssetClass, rates: &TaxRates) -> f64 {
+    match class {
+        AssetClass::Stock => rates.stock_sell,
+        AssetClass::Crypto => rates.crypto_sell,
+        // On-chain tokens are not traded through a registered exchange, so no final tax is withheld.
+        AssetClass::Token | AssetClass::Cash => 0.0,
+    }
+}
+
+pub fn build_report(ledger: &Ledger, year: i32) -> Result<TaxReport, String> {
+    let rates = TaxRates::for_year(year);
+    let replay = ledger.replay()?;
+    let mut lines: Vec<TaxLine> = replay.disposals.iter()
+        .filter(|d| d.date.year() == year && d.class != AssetClass::Cash)
+        .map(|d| TaxLine {
+            date: d.date, class: d.class, symbol: d.symbol.clone(), side: Side::Sell,
+            quantity: d.quantity, gross: d.gross, rate: sell_rate(d.class, &rates), gain: Some(d.gain()),
+        })
+        .collect();
+    if rates.crypto_buy > 0.0 {
+        lines.extend(ledger.trades.iter()
+            .filter(|t| t.date.year() == year && t.side == Side::Buy && t.class == AssetClass::Crypto)
+            .map(|t| TaxLine {
+                date: t.date, class: t.class, symbol: t.symbol.clone(), side: Side::Buy,
+                quantity: t.quantity, gross: t.gross(), rate: rates.crypto_buy, gain: None,
+            }));
+    }
+    lines.sort_by_key(|l| l.date);
+    Ok(TaxReport { year, rates, lines })
+}
+
+fn display_symbol(class: AssetClass, symbol: &str) -> String {
+    if class == AssetClass::Token { short_address(symbol) } else { symbol.to_string() }
+}
+
+fn signed(n: f64) -> String {
+    format!("{}Rp {}", if n < 0.0 { "-" } else { "+" }, format_number(n.abs()))
+}
+
+pub fn format_report(report: &TaxReport) -> String {
+    let stock = report.totals(AssetClass::Stock);
+    let crypto = report.totals(AssetClass::Crypto);
+    let token = report.totals(AssetClass::Token);
+    let r = report.rates;
+    let mut out = format!(
+        "💼 <b>LAPORAN PAJAK {}</b>\n========================\n🕌 <b>SAHAM IDX</b> (PPh final {:.2}%):\n• Nilai jual: <code>Rp {}</code>\n• PPh final: <code>Rp {}</code>\n• Realized: <code>{}</code>\n\n🪙 <b>KRIPTO EXCHANGE</b> (PPh 22 final {:.2}%{}):\n• Nilai jual: <code>Rp {}</code>\n• Nilai beli: <code>Rp {}</code>\n• Pajak final: <code>Rp {}</code>\n• Realized: <code>{}</code>\n\n⚡️ <b>TOKEN ON-CHAIN</b> (tidak dipotong final):\n• Nilai jual: <code>Rp {}</code>\n• Realized: <code>{}</code>\n\n🧾 <b>TOTAL PAJAK FINAL:</b> <code>Rp {}</code>\n",
+        report.year, r.stock_sell * 100.0,
+        format_number(stock.sell_gross), format_number(stock.tax), signed(stock.gain),
+        r.crypto_sell * 100.0, if r.crypto_buy > 0.0 { format!(", PPN beli {:.2}%", r.crypto_buy * 100.0) } else { String::new() },
+        format_number(crypto.sell_gross), format_number(crypto.buy_gross), format_number(crypto.tax), signed(crypto.gain),
+        format_number(token.sell_gross), signed(token.gain),
+        format_number(report.total_tax())
+    );
+
+    if report.lines.is_empty() {
+        out.push_str("\n<i>Tidak ada penjualan tercatat di tahun ini</i>\n");
+    } else {
+        out.push_str(&format!("\n📋 <b>TRANSAKSI ({})</b>\n", report.lines.len()));
+        for l in report.lines.iter().take(15) {
+            out.push_str(&format!(
+                "• <code>{}</code> {} <b>{}</b> {} <code>Rp {}</code> → pajak <code>Rp {}</code>{}\n",
+                l.date, l.class.emoji(), escape_html(&display_symbol(l.class, &l.symbol)),
+                if l.side == Side::Buy { "beli" } else { "jual" }, format_number(l.gross), format_number(l.tax()),
+                l.gain.map(|g| format!(" • {}", signed(g))).unwrap_or_default()
+            ));
+        }
+        if report.lines.len() > 15 {
+            out.push_str(&format!("<i>+{} transaksi lainnya (lihat ekspor)</i>\n", report.lines.len() - 15));
+        }
+    }
+    out.push_str("========================\n<i>💡 Estimasi dari /ledger, bukan nasihat pajak. Pajak final biasanya sudah dipotong broker/exchange; realized token on-chain dilaporkan di SPT.</i>");
+    out
+}
+
+pub fn report_table(report: &TaxReport) -> Table {
+    Table {
+        name: "pajak",
+        headers: vec!["date", "class", "symbol", "side", "quantity", "gross_idr", "rate", "tax_idr", "realized_idr"],
+        rows: report.lines.iter().map(|l| vec![
+            json!(l.date.to_string()), json!(l.class.code()), json!(l.symbol),
+            json!(if l.side == Side::Buy { "buy" } else { "sell" }), json!(l.quantity), json!(l.gross), json!(l.rate), json!(l.tax()), json!(l.gain),
+        ]).collect(),
+    }
+}
+
+// Plain-text version for printing or archiving next to the SPT.
+pub fn report_text(report: &TaxReport) -> String {
+    let mut out = format!("LAPORAN PAJAK & CAPITAL GAIN {}\n{}\n\n", report.year, "=".repeat(40));
+    for (class, title, rate) in [
+        (AssetClass::Stock, "SAHAM IDX", report.rates.stock_sell),
+        (AssetClass::Crypto, "KRIPTO EXCHANGE", report.rates.crypto_sell),
+        (AssetClass::Token, "TOKEN ON-CHAIN", 0.0),
+    ] {
+        let t = report.totals(class);
+        out.push_str(&format!(
+            "{} (tarif jual {:.2}%)\n  Nilai jual   : Rp {}\n  Nilai beli   : Rp {}\n  Pajak final  : Rp {}\n  Realized     : {}\n\n",
+            title, rate * 100.0, format_number(t.sell_gross), format_number(t.buy_gross), format_number(t.tax), signed(t.gain)
+        ));
+    }
+    out.push_str(&format!("TOTAL PAJAK FINAL: Rp {}\n\n", format_number(report.total_tax())));
+    out.push_str(&format!("{:<10}  {:<6}  {:<12}  {:<4}  {:>16}  {:>6}  {:>12}  {:>16}\n", "Tanggal", "Kelas", "Simbol", "Sisi", "Nilai (Rp)", "Tarif", "Pajak (Rp)", "Realized (Rp)"));
+    for l in &report.lines {
+        out.push_str(&format!(
+            "{:<10}  {:<6}  {:<12}  {:<4}  {:>16}  {:>5.2}%  {:>12}  {:>16}\n",
+            l.date, l.class.code(), display_symbol(l.class, &l.symbol), if l.side == Side::Buy { "beli" } else { "jual" },
+            format_number(l.gross), l.rate * 100.0, format_number(l.tax()),
+            l.gain.map(signed).unwrap_or_else(|| "-".to_string())
+        ));
+    }
+    out.push_str("\nEstimasi dari ledger manual, bukan nasihat pajak.\n");
+    out
+}
@@ -0,0 +1,276 @@
+use crate::api::{escape_html, format_compact, format_number};
+use crate::solana::{short_address, WSOL_MINT};
+use chrono::{TimeZone, Utc};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    EncodedTransaction, UiInstruction, UiMessage, UiParsedInstruction, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const DUST: f64 = 1e-9;
+
+// (program id, label, is DEX)
+const KNOWN_PROGRAMS: [(&str, &str, bool); 20] = [
+    ("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4", "Jupiter v6", true),
+    ("JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB", "Jupiter v4", true),
+    ("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", "Raydium AMM", true),
+    ("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK", "Raydium CLMM", true),
+    ("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C", "Raydium CPMM", true),
+    ("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc", "Orca Whirlpool", true),
+    ("9W959DqEETiGZocYWCQPaJ6sBmUzgfxXfqGeTEdp3aQP", "Orca v2", true),
+    ("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo", "Meteora DLMM", true),
+    ("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB", "Meteora Pools", true),
+    ("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P", "Pump.fun", true),
+    ("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY", "Phoenix", true),
+    ("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX", "OpenBook", true),
+    ("11111111111111111111111111111111", "System Program", false),
+    ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "Token Program", false),
+    ("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", "Token-2022", false),
+    ("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", "Associated Token", false),
+    ("ComputeBudget111111111111111111111111111111", "Compute Budget", false),
+    ("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "Memo", false),
+    ("Stake11111111111111111111111111111111111111", "Stake Program", false),
+    ("Vote111111111111111111111111111111111111111", "Vote Program", false),
+];
+
+pub fn program_label(id: &str) -> Option<(&'static str, bool)> {
+    KNOWN_PROGRAMS.iter().find(|p| p.0 == id).map(|p| (p.1, p.2))
+}
+
+// ==========================================
+// DECODING
+// ==========================================
+
+// `mint` is None for native SOL.
+#[derive(Debug, Clone)]
+pub struct BalanceChange {
+    pub owner: String,
+    pub mint: Option<String>,
+    pub delta: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TxSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub fee_payer: String,
+    pub fee: u64,
+    pub err: Option<String>,
+    pub compute_units: Option<u64>,
+    pub programs: Vec<String>,
+    pub changes: Vec<BalanceChange>,
+}
+
+impl TxSummary {
+    pub fn changes_for(&self, owner: &str) -> Vec<&BalanceChange> {
+        self.changes.iter().filter(|c| c.owner == owner).collect()
+    }
+
+    // Largest opposite-side move of the same asset by anyone else.
+    pub fn counterparty(&self, change: &BalanceChange) -> Option<&str> {
+        self.changes.iter()
+            .filter(|c| c.owner != change.owner && c.mint == change.mint && c.delta.signum() != change.delta.signum())
+            .max_by(|a, b| a.delta.abs().partial_cmp(&b.delta.abs()).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|c| c.owner.as_str())
+    }
+}
+
+fn instruction_program(ix: &UiInstruction, keys: &[String]) -> Option<String> {
+    match ix {
+        UiInstruction::Compiled(c) => keys.get(c.program_id_index as usize).cloned(),
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(p)) => Some(p.program_id.clone()),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(p)) => Some(p.program_id.clone()),
+    }
+}
+
+fn token_amounts(balances: Option<&Vec<UiTransactionTokenBalance>>) -> HashMap<(String, String), f64> {
+    let mut out = HashMap::new();
+    for b in balances.into_iter().flatten() {
+        let owner: Option<&String> = b.owner.as_ref().into();
+        let Some(owner) = owner else { continue };
+        let amount = b.ui_token_amount.ui_amount_string.parse::<f64>().unwrap_or(0.0);
+        *out.entry((owner.clone(), b.mint.clone())).or_insert(0.0) += amount;
+    }
+    out
+}
+
+pub async fn fetch_transaction(client: &RpcClient, signature: &str) -> Result<TxSummary, String> {
+    let sig = Signature::from_str(signature.trim()).map_err(|_| format!("Signature tidak valid: {}", signature.trim()))?;
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::JsonParsed),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = client.get_transaction_with_config(&sig, config).await.map_err(|e| format!("RPC error: {}", e))?;
+    let meta = tx.transaction.meta.ok_or_else(|| "Metadata transaksi tidak tersedia".to_string())?;
+    let EncodedTransaction::Json(ui_tx) = tx.transaction.transaction else {
+        return Err("Format transaksi tidak didukung".to_string());
+    };
+    let UiMessage::Parsed(message) = ui_tx.message else {
+        return Err("Format transaksi tidak didukung".to_string());
+    };
+    let keys: Vec<String> = message.account_keys.iter().map(|k| k.pubkey.clone()).collect();
+    let fee_payer = keys.first().cloned().unwrap_or_default();
+
+    let mut changes = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let pre = meta.pre_balances.get(i).copied().unwrap_or(0) as i64;
+        let post = meta.post_balances.get(i).copied().unwrap_or(0) as i64;
+        // The network fee is reported separately, so it is taken out of the payer's SOL change.
+        let fee = if i == 0 { meta.fee as i64 } else { 0 };
+        let delta = lamports_to_sol((post - pre + fee).unsigned_abs()) * (post - pre + fee).signum() as f64;
+        if delta.abs() > DUST {
+            changes.push(BalanceChange { owner: key.clone(), mint: None, delta });
+        }
+    }
+
+    let inner: Option<&Vec<_>> = meta.inner_instructions.as_ref().into();
+    let mut programs: Vec<String> = Vec::new();
+    let all_instructions = message.instructions.iter().chain(inner.into_iter().flatten().flat_map(|i| i.instructions.iter()));
+    for program in all_instructions.filter_map(|ix| instruction_program(ix, &keys)) {
+        if !programs.contains(&program) { programs.push(program); }
+    }
+
+    let pre_tokens = token_amounts(Option::from(meta.pre_token_balances.as_ref()));
+    let post_tokens = token_amounts(Option::from(meta.post_token_balances.as_ref()));
+    let mut token_keys: Vec<&(String, String)> = pre_tokens.keys().chain(post_tokens.keys()).collect();
+    token_keys.sort();
+    token_keys.dedup();
+    for key in token_keys {
+        let delta = post_tokens.get(key).copied().unwrap_or(0.0) - pre_tokens.get(key).copied().unwrap_or(0.0);
+        if delta.abs() > DUST {
+            changes.push(BalanceChange { owner: key.0.clone(), mint: Some(key.1.clone()), delta });
+        }
+    }
+
+    Ok(TxSummary {
+        signature: signature.trim().to_string(),
+        slot: tx.slot,
+        block_time: tx.block_time,
+        fee_payer,
+        fee: meta.fee,
+        err: meta.err.map(|e| e.to_string()),
+        compute_units: Option::from(meta.compute_units_consumed),
+        programs,
+        changes,
+    })
+}
+
+// ==========================================
+// WALLET ACTIVITY
+// ==========================================
+
+pub fn asset_label(mint: &Option<String>, prices: &HashMap<String, (String, f64)>) -> String {
+    match mint {
+        None => "SOL".to_string(),
+        Some(m) => prices.get(m).map(|p| escape_html(&p.0)).unwrap_or_else(|| short_address(m)),
+    }
+}
+
+pub fn asset_price(mint: &Option<String>, prices: &HashMap<String, (String, f64)>) -> Option<f64> {
+    prices.get(mint.as_deref().unwrap_or(WSOL_MINT)).map(|p| p.1)
+}
+
+pub fn format_activity(owner: &str, tx: &TxSummary, prices: &HashMap<String, (String, f64)>) -> Option<String> {
+    let changes = tx.changes_for(owner);
+    if changes.is_empty() {
+        return None;
+    }
+    let has_in = changes.iter().any(|c| c.delta > 0.0);
+    let has_out = changes.iter().any(|c| c.delta < 0.0);
+    let kind = match (has_in, has_out) {
+        (true, true) => "🔄 SWAP",
+        (true, false) => "📥 MASUK",
+        _ => "📤 KELUAR",
+    };
+
+    let mut out = format!(
+        "🔔 <b>WALLET ACTIVITY</b>\n========================\n👛 Wallet: <code>{}</code>\n{} {}\n\n",
+        short_address(owner), kind, if tx.err.is_some() { "❌ (gagal)" } else { "" }
+    );
+    for c in &changes {
+        let value = asset_price(&c.mint, prices).map(|p| format!(" ≈ <code>${:.2}</code>", p * c.delta.abs())).unwrap_or_default();
+        out.push_str(&format!(
+            "• <code>{}{}</code> <b>{}</b>{}\n",
+            if c.delta > 0.0 { "+" } else { "-" }, format_compact(c.delta.abs()), asset_label(&c.mint, prices), value
+        ));
+    }
+    if kind != "🔄 SWAP" {
+        if let Some(cp) = changes.first().and_then(|c| tx.counterparty(c)) {
+            out.push_str(&format!("👤 {}: <code>{}</code>\n", if has_in { "Dari" } else { "Ke" }, cp));
+        }
+    }
+    if tx.fee_payer == owner {
+        out.push_str(&format!("⛽ Fee: <code>{:.6} SOL</code>\n", lamports_to_sol(tx.fee)));
+    }
+    if let Some(t) = tx.block_time.and_then(|t| Utc.timestamp_opt(t, 0).single()) {
+        out.push_str(&format!("🕒 {}\n", t.format("%Y-%m-%d %H:%M:%S UTC")));
+    }
+    out.push_str(&format!(
+        "========================\n<a href=\"https://solscan.io/tx/{}\">🔍 Lihat di Solscan</a>",
+        tx.signature
+    ));
+    Some(out)
+}
+
+// ==========================================
+// TRANSACTION BREAKDOWN
+// ==========================================
+
+const MAX_OWNERS: usize = 8;
+
+pub fn format_transaction(tx: &TxSummary, prices: &HashMap<String, (String, f64)>) -> String {
+    let status = match &tx.err {
+        None => "✅ Sukses".to_string(),
+        Some(e) => format!("❌ Gagal: <code>{}</code>", escape_html(e)),
+    };
+    let time = tx.block_time.and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let mut out = format!(
+        "🧾 <b>TRANSACTION</b>\n========================\n• Signature: <code>{}</code>\n• Status: {}\n• Slot: <code>{}</code> • {}\n• Fee Payer: <code>{}</code>\n• Fee: <code>{:.6} SOL</code>\n• Compute Units: <code>{}</code>\n\n⚖️ <b>BALANCE CHANGES:</b>\n",
+        short_address(&tx.signature), status, tx.slot, time, tx.fee_payer, lamports_to_sol(tx.fee),
+        tx.compute_units.map(|u| format_number(u as f64)).unwrap_or_else(|| "N/A".to_string())
+    );
+
+    let mut owners: Vec<&str> = Vec::new();
+    for c in &tx.changes {
+        if !owners.contains(&c.owner.as_str()) { owners.push(&c.owner); }
+    }
+    if owners.is_empty() {
+        out.push_str("<i>Tidak ada perubahan saldo</i>\n");
+    }
+    for owner in owners.iter().take(MAX_OWNERS) {
+        out.push_str(&format!("<b>{}</b>{}\n", short_address(owner), if *owner == tx.fee_payer { " (payer)" } else { "" }));
+        for c in tx.changes_for(owner) {
+            let value = asset_price(&c.mint, prices).map(|p| format!(" ≈ <code>${:.2}</code>", p * c.delta.abs())).unwrap_or_default();
+            out.push_str(&format!(
+                "  • <code>{}{}</code> {}{}\n",
+                if c.delta > 0.0 { "+" } else { "-" }, format_compact(c.delta.abs()), asset_label(&c.mint, prices), value
+            ));
+        }
+    }
+    if owners.len() > MAX_OWNERS {
+        out.push_str(&format!("<i>… dan {} akun lainnya</i>\n", owners.len() - MAX_OWNERS));
+    }
+
+    out.push_str("\n🧩 <b>PROGRAMS:</b>\n");
+    for p in &tx.programs {
+        match program_label(p) {
+            Some((label, true)) => out.push_str(&format!("• 🔄 <b>{}</b> (DEX)\n", label)),
+            Some((label, false)) => out.push_str(&format!("• {}\n", label)),
+            None => out.push_str(&format!("• <code>{}</code>\n", short_address(p))),
+        }
+    }
+    out.push_str(&format!(
+        "========================\n<a href=\"https://solscan.io/tx/{}\">🔍 Lihat di Solscan</a>",
+        tx.signature
+    ));
+    out
+}
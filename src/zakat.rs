@@ -0,0 +1,88 @@
+use crate::api::format_number;
+
+// ==========================================
+// ZAKAT MAAL
+// ==========================================
+
+pub const NISAB_GRAMS: f64 = 85.0;
+pub const RATE: f64 = 0.025;
+pub const HAUL_DAYS: i64 = 354;
+
+#[derive(Debug, Clone)]
+pub struct ZakatAsset {
+    pub label: String,
+    pub class: &'static str,
+    pub value_idr: f64,
+    pub since: i64,
+}
+
+impl ZakatAsset {
+    pub fn held_days(&self, now: i64) -> i64 {
+        (now - self.since).max(0) / 86_400
+    }
+
+    pub fn haul_complete(&self, now: i64) -> bool {
+        self.held_days(now) >= HAUL_DAYS
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZakatReport {
+    pub gold_price: f64,
+    pub assets: Vec<ZakatAsset>,
+    pub now: i64,
+}
+
+impl ZakatReport {
+    pub fn nisab(&self) -> f64 {
+        NISAB_GRAMS * self.gold_price
+    }
+
+    pub fn total(&self) -> f64 {
+        self.assets.iter().map(|a| a.value_idr).sum()
+    }
+
+    pub fn haul_total(&self) -> f64 {
+        self.assets.iter().filter(|a| a.haul_complete(self.now)).map(|a| a.value_idr).sum()
+    }
+
+    pub fn due(&self) -> f64 {
+        if self.haul_total() >= self.nisab() { self.haul_total() * RATE } else { 0.0 }
+    }
+}
+
+// `portfolio` names the holdings being assessed; simulator and real assets are never mixed in one haul total.
+pub fn format_report(report: &ZakatReport, gold_source: &str, portfolio: &str) -> String {
+    let mut out = format!(
+        "🕋 <b>ZAKAT MAAL CALCULATOR</b>\n========================\n📂 Portofolio: <b>{}</b>\n\n🥇 <b>NISAB:</b>\n• Harga Emas: <code>Rp {}/gr</code> ({})\n• Nisab {} gr: <code>Rp {}</code>\n\n📦 <b>RINCIAN ASET:</b>\n",
+        portfolio, format_number(report.gold_price), gold_source, NISAB_GRAMS, format_number(report.nisab())
+    );
+
+    if report.assets.is_empty() {
+        out.push_str("<i>Belum ada aset tercatat</i>\n");
+    }
+    for a in &report.assets {
+        let haul = if a.haul_complete(report.now) {
+            "✅ haul".to_string()
+        } else {
+            format!("⏳ {} hari lagi", HAUL_DAYS - a.held_days(report.now))
+        };
+        let zakat = if a.haul_complete(report.now) && report.due() > 0.0 { a.value_idr * RATE } else { 0.0 };
+        out.push_str(&format!(
+            "• {} <b>{}</b>: <code>Rp {}</code>\n   {} • zakat <code>Rp {}</code>\n",
+            a.class, a.label, format_number(a.value_idr), haul, format_number(zakat)
+        ));
+    }
+
+    let status = if report.haul_total() >= report.nisab() {
+        "✅ <b>WAJIB ZAKAT</b>"
+    } else {
+        "ℹ️ <b>BELUM MENCAPAI NISAB</b>"
+    };
+    out.push_str(&format!(
+        "\n💰 <b>TOTAL:</b>\n• Total Aset: <code>Rp {}</code>\n• Sudah Haul: <code>Rp {}</code>\n• Status: {}\n• Zakat ({}%): <code>Rp {}</code>\n========================\n<i>💡 Haul = {} hari (1 tahun hijriah) sejak aset dimiliki</i>",
+        format_number(report.total()), format_number(report.haul_total()), status,
+        RATE * 100.0, format_number(report.due()), HAUL_DAYS
+    ));
+    out
+}